@@ -0,0 +1,328 @@
+//! Derive macro for the `Token` trait in `automata_rust`.
+//!
+//! The patterns are parsed when the macro is expanded,
+//! so an invalid pattern is a compile error pointing at the pattern.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::ParseStream, parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, Ident,
+    LitStr,
+};
+
+/// Skip pattern used unless `#[token(skip = "...")]` is given on the enum.
+const DEFAULT_SKIP: &str = r"(\n|\t|\ )*";
+
+/// Implement `Token` for an enum.
+///
+/// ```ignore
+/// #[derive(Debug, Token)]
+/// #[token(skip = r"(\n|\t|\r|\ )*")]
+/// enum ExprToken {
+///     #[token(r"(a-z|A-Z)(a-z|A-Z|0-9)*")]
+///     Var,
+///     #[token(r"(0-9)+", convert = |s| Num(s.parse().unwrap()))]
+///     Num(i64),
+///     #[token(eof)]
+///     Eof,
+/// }
+/// ```
+///
+/// Variants are tried in declaration order when two patterns match the same length.
+/// Each variant has at most one pattern, use `|` to match several.
+/// Variants with fields must provide a `convert` function from the matched text.
+/// Variants without a `#[token]` attribute are never produced by the lexer.
+#[proc_macro_derive(Token, attributes(token))]
+pub fn derive_token(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct VariantAttr {
+    pattern: Option<LitStr>,
+    convert: Option<Expr>,
+    eof: bool,
+}
+
+impl VariantAttr {
+    /// Parse `#[token("pattern")]`, `#[token("pattern", convert = expr)]` or `#[token(eof)]`.
+    fn parse(attr: &Attribute) -> syn::Result<Self> {
+        attr.parse_args_with(|input: ParseStream| {
+            let mut this = Self::default();
+
+            if input.peek(LitStr) {
+                let pattern: LitStr = input.parse()?;
+                validate(&pattern)?;
+                this.pattern = Some(pattern);
+
+                if input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+                    let key: Ident = input.parse()?;
+                    if key != "convert" {
+                        return Err(syn::Error::new(key.span(), "expected `convert = ...`"));
+                    }
+                    input.parse::<syn::Token![=]>()?;
+                    this.convert = Some(input.parse()?);
+                    input.parse::<Option<syn::Token![,]>>()?;
+                }
+            } else {
+                let key: Ident = input.parse()?;
+                if key != "eof" {
+                    return Err(syn::Error::new(key.span(), "expected a pattern or `eof`"));
+                }
+                this.eof = true;
+            }
+
+            Ok(this)
+        })
+    }
+}
+
+/// Parse `#[token(skip = "pattern")]` on the enum.
+fn parse_skip(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut skip = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("token")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                let pattern: LitStr = meta.value()?.parse()?;
+                validate(&pattern)?;
+                skip = Some(pattern);
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip = \"...\"`"))
+            }
+        })?;
+    }
+    Ok(skip)
+}
+
+/// Check that the pattern can be parsed.
+fn validate(pattern: &LitStr) -> syn::Result<()> {
+    let value = pattern.value();
+    match value.parse::<automata_rust_parse::Postfix>() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(syn::Error::new(
+            pattern.span(),
+            format!("invalid pattern: {}", e.render(&value)),
+        )),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            name.span(),
+            "Token can only be derived for enums",
+        ));
+    };
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "Token cannot be derived for generic enums",
+        ));
+    }
+
+    let skip = parse_skip(&input.attrs)?.map_or_else(|| DEFAULT_SKIP.to_string(), |s| s.value());
+
+    let mut eof = quote!(None);
+    let mut rules = vec![];
+    let mut arms = vec![];
+
+    for variant in &data.variants {
+        let ident = &variant.ident;
+        let label = ident.to_string();
+        let mut has_pattern = false;
+
+        for token in variant.attrs.iter().filter(|a| a.path().is_ident("token")) {
+            let attr = VariantAttr::parse(token)?;
+
+            if attr.eof {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "the eof variant can't have any fields",
+                    ));
+                }
+                eof = quote!(Some(Self::#ident));
+                continue;
+            }
+
+            let Some(pattern) = attr.pattern else {
+                continue;
+            };
+            if has_pattern {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "a variant can only have one pattern, join the patterns with `|`",
+                ));
+            }
+            has_pattern = true;
+
+            let construct = match (attr.convert, &variant.fields) {
+                (Some(convert), _) => quote! {{
+                    #[allow(unused_imports)]
+                    use #name::*;
+                    let convert: fn(&str) -> Self = #convert;
+                    convert(text)
+                }},
+                (None, Fields::Unit) => quote!(Self::#ident),
+                (None, _) => return Err(syn::Error::new(
+                    ident.span(),
+                    "variants with fields need a converter, `#[token(\"...\", convert = |s| ...)]`",
+                )),
+            };
+
+            rules.push(quote! {
+                (#label.into(), <NFA as Language>::try_from_language(#pattern).unwrap())
+            });
+            arms.push(quote!(#label => #construct,));
+        }
+    }
+
+    if rules.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "at least one variant needs a `#[token(\"...\")]` attribute",
+        ));
+    }
+
+    Ok(quote! {
+        const _: () = {
+            use ::std::sync::OnceLock;
+            use ::automata_rust::dfa::TokenDFA;
+            use ::automata_rust::language::Language;
+            use ::automata_rust::lexer::token::Token;
+            use ::automata_rust::nfa::{NFASet, NFA};
+
+            impl Token for #name {
+                fn eof() -> Option<Self> {
+                    #eof
+                }
+
+                fn get_skip_reg() -> &'static str {
+                    #skip
+                }
+
+                fn get_skip_dfa() -> &'static TokenDFA {
+                    static SKIP_DFA: OnceLock<TokenDFA> = OnceLock::new();
+                    SKIP_DFA.get_or_init(|| {
+                        let skip = <NFA as Language>::try_from_language(#skip).unwrap();
+                        NFASet::build(vec![("skip".into(), skip)]).unwrap().determinize()
+                    })
+                }
+
+                fn get_token_set() -> &'static NFASet {
+                    static TOKEN_SET: OnceLock<NFASet> = OnceLock::new();
+                    TOKEN_SET.get_or_init(|| NFASet::build(vec![#(#rules),*]).unwrap())
+                }
+
+                fn get_token_dfa() -> &'static TokenDFA {
+                    static TOKEN_DFA: OnceLock<TokenDFA> = OnceLock::new();
+                    TOKEN_DFA.get_or_init(|| Self::get_token_set().determinize())
+                }
+
+                // `text` is unused when no variant has a converter
+                #[allow(unused_variables)]
+                fn token_from_match(label: &'static str, text: &str) -> Self {
+                    match label {
+                        #(#arms)*
+                        _ => unreachable!("No mapping for group: {label}"),
+                    }
+                }
+            }
+        };
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        expand(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn expand_enum() {
+        let tokens = expand(&parse_quote! {
+            enum T {
+                #[token("(a-z)+")]
+                Ident,
+                #[token("(0-9)+", convert = |s| Num(s.parse().unwrap()))]
+                Num(i64),
+                #[token(eof)]
+                Eof,
+                Other,
+            }
+        })
+        .unwrap()
+        .to_string();
+
+        assert!(tokens.contains("\"Ident\" => Self :: Ident"));
+        assert!(tokens.contains("Some (Self :: Eof)"));
+        assert!(!tokens.contains("\"Other\""));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            error(parse_quote! {
+                enum T {
+                    #[token("(a-z")]
+                    Ident,
+                }
+            }),
+            "invalid pattern: Unmatched '('\n | (a-z\n | ^"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[token(skip = "a|")]
+                enum T {
+                    #[token("a")]
+                    A,
+                }
+            }),
+            "invalid pattern: Unexpected EOF\n | a|\n |   ^"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                enum T {
+                    #[token("(0-9)+")]
+                    Num(i64),
+                }
+            }),
+            "variants with fields need a converter, `#[token(\"...\", convert = |s| ...)]`"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                enum T {
+                    #[token("a")]
+                    #[token("b")]
+                    A,
+                }
+            }),
+            "a variant can only have one pattern, join the patterns with `|`"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                enum T {
+                    A,
+                }
+            }),
+            "at least one variant needs a `#[token(\"...\")]` attribute"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                struct T;
+            }),
+            "Token can only be derived for enums"
+        );
+    }
+}
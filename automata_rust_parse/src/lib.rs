@@ -0,0 +1,322 @@
+//! Parser for the regular expressions used by `automata_rust`.
+//!
+//! Shared by the main crate and the `Token` derive, which validates the patterns when it is expanded.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::{format, string::String};
+use core::iter::Peekable;
+use core::ops::Range;
+use core::str::CharIndices;
+
+mod lit;
+mod postfix;
+mod token;
+
+pub use lit::Lit;
+pub use postfix::Postfix;
+pub use token::Token;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    Unmatched(&'static str),
+    ParsingStopped(Token),
+    InvalidPrefix(Token),
+    InvalidRange {
+        found: String,
+        expected: &'static str,
+    },
+    UnexpectedEof,
+}
+
+impl core::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ParsingStopped(token) => write!(f, "Parsing stopped at token: `{token}`"),
+            Self::Unmatched(s) => write!(f, "Unmatched '{s}'"),
+            Self::InvalidPrefix(s) => write!(f, "Token '{s}' cannot appear as a prefix"),
+            Self::InvalidRange { found, expected } => write!(
+                f,
+                "Invalid group: Expected token '{expected}' but found '{found}'"
+            ),
+            Self::UnexpectedEof => "Unexpected EOF".fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Bytes of the pattern which caused the error.
+    pub span: Range<usize>,
+}
+
+impl ParseError {
+    #[must_use]
+    pub fn new(kind: ParseErrorKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+
+    /// Show the error together with the line of `pattern` where it occurred.
+    /// The offending part of the line is underlined:
+    ///
+    /// ```text
+    /// Unmatched '('
+    ///  | A|(B?
+    ///  |   ^
+    /// ```
+    #[must_use]
+    pub fn render(&self, pattern: &str) -> String {
+        let start = self.span.start.min(pattern.len());
+        let line_start = pattern[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = pattern[start..]
+            .find('\n')
+            .map_or(pattern.len(), |i| start + i);
+        let end = self.span.end.clamp(start, line_end);
+
+        let column = pattern[line_start..start].chars().count();
+        let width = pattern[start..end].chars().count().max(1);
+
+        format!(
+            "{}\n | {}\n | {}{}",
+            self.kind,
+            &pattern[line_start..line_end],
+            " ".repeat(column),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.span.start)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+#[derive(Debug)]
+struct Lexer<'i> {
+    input: Peekable<CharIndices<'i>>,
+    /// Length of the input in bytes.
+    len: usize,
+    queue: VecDeque<(Token, Range<usize>)>,
+}
+
+impl<'i> Lexer<'i> {
+    #[must_use]
+    fn new(input: &'i str) -> Self {
+        Self {
+            input: input.char_indices().peekable(),
+            len: input.len(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Error for when the input ended but more tokens were expected.
+    #[must_use]
+    fn eof_error(&self) -> ParseError {
+        ParseError::new(ParseErrorKind::UnexpectedEof, self.len..self.len)
+    }
+
+    fn peek(&mut self) -> Result<Option<&(Token, Range<usize>)>, ParseError> {
+        if self.queue.front().is_some() {
+            return Ok(self.queue.front());
+        }
+
+        while let Some((start, next)) = self.input.next() {
+            if next.is_whitespace() {
+                continue;
+            }
+
+            let mut end = start + next.len_utf8();
+
+            // True if we need to insert an implicit concatenation into the token stream
+            let mut needs_concat = true;
+            let next = match next {
+                '(' => {
+                    needs_concat = false;
+                    Token::OParen
+                }
+                '|' => {
+                    needs_concat = false;
+                    Token::Union
+                }
+                '-' => {
+                    needs_concat = false;
+                    Token::Range
+                }
+                ')' => Token::CParen,
+                '*' | '+' | '?' => {
+                    // A '?' right after a quantifier makes it lazy
+                    let lazy = self.input.next_if(|&(_, c)| c == '?').is_some();
+                    if lazy {
+                        end += 1;
+                    }
+                    match (next, lazy) {
+                        ('*', false) => Token::KleeneS,
+                        ('+', false) => Token::KleeneP,
+                        ('?', false) => Token::Optional,
+                        ('*', true) => Token::LazyKleeneS,
+                        ('+', true) => Token::LazyKleeneP,
+                        (_, _) => Token::LazyOptional,
+                    }
+                }
+                '$' => {
+                    needs_concat = false;
+                    Token::Eof
+                }
+                '\\' => {
+                    if let Some((i, c)) = self.input.next() {
+                        end = i + c.len_utf8();
+                        // TODO: Might be more than these...
+                        let lit = match c {
+                            'n' => Lit::Char('\n'),
+                            't' => Lit::Char('\t'),
+                            'r' => Lit::Char('\r'),
+                            _ => Lit::Char(c),
+                        };
+                        Token::Lit(lit)
+                    } else {
+                        return Err(ParseError::new(ParseErrorKind::UnexpectedEof, start..end));
+                    }
+                }
+                c => Token::Lit(Lit::Char(c)),
+            };
+
+            if needs_concat {
+                while let Some(&(i, c)) = self.input.peek() {
+                    if c.is_whitespace() {
+                        self.input.next();
+                        continue;
+                    }
+
+                    if !matches!(c, ')' | '*' | '+' | '|' | '?' | '-') {
+                        // The implicit concatenation is placed right before the next token
+                        self.queue.push_back((Token::Concat, i..i));
+                    }
+
+                    break;
+                }
+            }
+
+            self.queue.push_front((next, start..end));
+            return Ok(self.queue.front());
+        }
+
+        Ok(None)
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<(Token, Range<usize>), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peek() {
+            // Peek inserts the next token into the queue
+            Ok(Some(_)) => self.queue.pop_front().map(Ok),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        // assert_eq!("A".parse::<Postfix>().unwrap().to_string(), "A");
+        assert_eq!(
+            r#" \nA\t
+                   "#
+            .parse::<Postfix>()
+            .unwrap()
+            .to_string(),
+            r"(\n(A\t))"
+        );
+        assert_eq!(
+            "A? B|C".parse::<Postfix>().unwrap().to_string(),
+            "(((A?)B)|C)"
+        );
+        assert_eq!(
+            "AB|((A|C) B|C?)".parse::<Postfix>().unwrap().to_string(),
+            "((AB)|(((A|C)B)|(C?)))"
+        );
+        assert_eq!(
+            "A? | B* +".parse::<Postfix>().unwrap().to_string(),
+            "((A?)|((B*)+))"
+        );
+        assert_eq!(
+            "((((( (A) )))?))".parse::<Postfix>().unwrap().to_string(),
+            "(A?)"
+        );
+        assert_eq!(
+            "(AC?) (B|C?A)".parse::<Postfix>().unwrap().to_string(),
+            "((A(C?))(B|((C?)A)))"
+        );
+        assert_eq!(
+            "(A-Z|a-z)(A-Za-z0-9)*"
+                .parse::<Postfix>()
+                .unwrap()
+                .to_string(),
+            "(((A-Z)|(a-z))(((A-Z)((a-z)(0-9)))*))"
+        );
+
+        assert_eq!(
+            "\"A*?\" B+? C??".parse::<Postfix>().unwrap().to_string(),
+            "(\\\"((A*?)(\\\"((B+?)(C??)))))"
+        );
+        assert_eq!("A? ?".parse::<Postfix>().unwrap().to_string(), "((A?)?)");
+
+        assert!("A|(B?".parse::<Postfix>().is_err());
+        assert!("A)|B?".parse::<Postfix>().is_err());
+        assert!("A|?".parse::<Postfix>().is_err());
+        assert!("|B".parse::<Postfix>().is_err());
+        assert!("(A))|(B)?".parse::<Postfix>().is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let err = |pattern: &str| pattern.parse::<Postfix>().unwrap_err();
+
+        assert_eq!(
+            err("A|(B?"),
+            ParseError::new(ParseErrorKind::Unmatched("("), 2..3)
+        );
+        assert_eq!(
+            err("A)|B?"),
+            ParseError::new(ParseErrorKind::ParsingStopped(Token::CParen), 1..2)
+        );
+        assert_eq!(
+            err("A|?"),
+            ParseError::new(ParseErrorKind::InvalidPrefix(Token::Optional), 2..3)
+        );
+        assert_eq!(
+            err("AB|"),
+            ParseError::new(ParseErrorKind::UnexpectedEof, 3..3)
+        );
+        assert_eq!(
+            err(r"AB\"),
+            ParseError::new(ParseErrorKind::UnexpectedEof, 2..3)
+        );
+        assert_eq!(err("x(A*-B)").span, 2..6);
+        assert_eq!(
+            err("A|*?"),
+            ParseError::new(ParseErrorKind::InvalidPrefix(Token::LazyKleeneS), 2..4)
+        );
+
+        assert_eq!(
+            err("AB | (C\n  | D*-E)").render("AB | (C\n  | D*-E)"),
+            "Invalid group: Expected token '(c-c)' but found '(*-E)'\n |   | D*-E)\n |     ^^^^"
+        );
+        assert_eq!(
+            err("\u{e5}\u{e4}|\u{f6}?(").render("\u{e5}\u{e4}|\u{f6}?("),
+            "Unexpected EOF\n | \u{e5}\u{e4}|\u{f6}?(\n |       ^"
+        );
+    }
+}
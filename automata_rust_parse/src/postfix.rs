@@ -1,4 +1,7 @@
-use super::{Lexer, Lit, ParseError, Token};
+use alloc::{format, vec, vec::Vec};
+use core::ops::Range;
+
+use super::{Lexer, Lit, ParseError, ParseErrorKind, Token};
 
 /// Tokens in Reverse Polish Notation.
 #[derive(Debug, PartialEq, Eq)]
@@ -6,14 +9,14 @@ pub struct Postfix {
     pub tokens: Vec<Token>,
 }
 
-impl std::str::FromStr for Postfix {
+impl core::str::FromStr for Postfix {
     type Err = ParseError;
 
     fn from_str(infix: &str) -> Result<Self, Self::Err> {
         let input = &mut Lexer::new(infix);
-        let tokens = Self::parse_expr(input, 0)?;
-        if let Some(token) = input.next() {
-            Err(ParseError::ParsingStopped(token))
+        let (tokens, _) = Self::parse_expr(input, 0)?;
+        if let Some((token, span)) = input.next().transpose()? {
+            Err(ParseError::new(ParseErrorKind::ParsingStopped(token), span))
         } else {
             Ok(Self { tokens })
         }
@@ -22,37 +25,44 @@ impl std::str::FromStr for Postfix {
 
 impl Postfix {
     /// Parse a list of token in postfix notation using [Pratt Parsing].
+    /// Returns the tokens together with the span of the parsed expression.
     ///
     /// [Pratt Parsing]: <https://en.wikipedia.org/wiki/Operator-precedence_parser#Pratt_parsing>
-    fn parse_expr(input: &mut Lexer<'_>, prec: usize) -> Result<Vec<Token>, ParseError> {
-        let mut lhs = match input.next().ok_or(ParseError::UnexpectedEof)? {
+    fn parse_expr(
+        input: &mut Lexer<'_>,
+        prec: usize,
+    ) -> Result<(Vec<Token>, Range<usize>), ParseError> {
+        let (token, mut span) = input.next().transpose()?.ok_or_else(|| input.eof_error())?;
+        let mut lhs = match token {
             Token::Lit(lit) => vec![Token::Lit(lit)],
             Token::Eof => vec![Token::Eof],
             Token::OParen => {
-                let lhs = Self::parse_expr(input, 0)?;
-                if input.next() != Some(Token::CParen) {
-                    return Err(ParseError::Unmatched("("));
+                let (lhs, _) = Self::parse_expr(input, 0)?;
+                match input.next().transpose()? {
+                    Some((Token::CParen, close)) => span.end = close.end,
+                    _ => return Err(ParseError::new(ParseErrorKind::Unmatched("("), span)),
                 }
                 lhs
             }
-            token => return Err(ParseError::InvalidPrefix(token)),
+            token => return Err(ParseError::new(ParseErrorKind::InvalidPrefix(token), span)),
         };
 
-        while let Some(token) = input.peek() {
+        while let Some((token, _)) = input.peek()? {
             if let Some(post_prec) = token.postfix_precedence() {
                 if post_prec < prec {
                     break;
                 }
-                let token = input.next().unwrap();
+                let (token, op) = input.next().unwrap()?;
 
                 lhs.push(token);
+                span.end = op.end;
             } else if let Some((left_prec, right_prec)) = token.infix_precedence() {
                 if left_prec < prec {
                     break;
                 }
-                let token = input.next().unwrap();
+                let (token, _) = input.next().unwrap()?;
 
-                let mut rhs = Self::parse_expr(input, right_prec)?;
+                let (mut rhs, rhs_span) = Self::parse_expr(input, right_prec)?;
                 if token == Token::Range {
                     let left = lhs.pop().unwrap();
                     let right = rhs.pop().unwrap();
@@ -61,26 +71,30 @@ impl Postfix {
                     {
                         lhs.push(Token::Lit(Lit::Range(*lower..=*upper)));
                     } else {
-                        return Err(ParseError::InvalidRange {
-                            found: format!("({left}-{right})"),
-                            expected: "(c-c)",
-                        });
+                        return Err(ParseError::new(
+                            ParseErrorKind::InvalidRange {
+                                found: format!("({left}-{right})"),
+                                expected: "(c-c)",
+                            },
+                            span.start..rhs_span.end,
+                        ));
                     }
                 } else {
                     lhs.append(&mut rhs);
                     lhs.push(token);
                 }
+                span.end = rhs_span.end;
             } else {
                 break;
             }
         }
 
-        Ok(lhs)
+        Ok((lhs, span))
     }
 }
 
-impl std::fmt::Display for Postfix {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Postfix {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut stack = vec![];
 
         for token in &self.tokens {
@@ -88,7 +102,12 @@ impl std::fmt::Display for Postfix {
                 Token::Eof | Token::OParen | Token::CParen | Token::Lit(_) => {
                     stack.push(format!("{token}"));
                 }
-                Token::Optional | Token::KleeneS | Token::KleeneP => {
+                Token::Optional
+                | Token::KleeneS
+                | Token::KleeneP
+                | Token::LazyOptional
+                | Token::LazyKleeneS
+                | Token::LazyKleeneP => {
                     let lhs = stack.pop().unwrap();
                     stack.push(format!("({lhs}{token})"));
                 }
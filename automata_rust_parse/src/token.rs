@@ -18,6 +18,12 @@ pub enum Token {
     Union,
     /// Optional '?'
     Optional,
+    /// Lazy Kleene Star '*?'
+    LazyKleeneS,
+    /// Lazy Kleene Plus '+?'
+    LazyKleeneP,
+    /// Lazy Optional '??'
+    LazyOptional,
     /// Range '-'
     Range,
     /// Singelton and group
@@ -38,15 +44,15 @@ impl Token {
     #[must_use]
     pub fn postfix_precedence(&self) -> Option<usize> {
         match self {
-            Self::KleeneP | Self::KleeneS => Some(10),
-            Self::Optional => Some(9),
+            Self::KleeneP | Self::KleeneS | Self::LazyKleeneP | Self::LazyKleeneS => Some(10),
+            Self::Optional | Self::LazyOptional => Some(9),
             _ => None,
         }
     }
 }
 
-impl std::fmt::Display for Token {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Token {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::OParen => "(".fmt(f),
             Self::CParen => ")".fmt(f),
@@ -55,6 +61,9 @@ impl std::fmt::Display for Token {
             Self::Concat => "".fmt(f),
             Self::Union => "|".fmt(f),
             Self::Optional => "?".fmt(f),
+            Self::LazyKleeneS => "*?".fmt(f),
+            Self::LazyKleeneP => "+?".fmt(f),
+            Self::LazyOptional => "??".fmt(f),
             Self::Range => "-".fmt(f),
             Self::Eof => "$".fmt(f),
             Self::Lit(c) => c.fmt(f),
@@ -0,0 +1,26 @@
+use automata_rust::{language::Language, nfa::NFA};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// This is not a good benchmark.
+/// It's a simple test to check whether a particular optimization has any effect.
+///
+/// Previous iterations, with the nightly `#[bench]` harness:
+/// - 10,510,434 ns/iter (+/- 210,810)
+/// - 10,631,001 ns/iter (+/- 198,757)   After chaning is_match to return `Option<usize>`.
+/// - 18,495,653 ns/iter (+/- 1,023,148) After adding capturing groups and char-classes.
+/// - 11,306,364 ns/iter (+/- 419,921)   Add &mut to matches HashMap, avoids redundant loop.
+fn bench_matches(c: &mut Criterion) {
+    const N: usize = 250;
+
+    let pattern = "A?".repeat(N) + &"A".repeat(N);
+    let input = &"A".repeat(N);
+
+    let nfa: NFA = NFA::try_from_language(pattern).unwrap();
+
+    assert!(!nfa.is_match(input).is_empty());
+
+    c.bench_function("nfa_matches", |b| b.iter(|| !nfa.is_match(input).is_empty()));
+}
+
+criterion_group!(benches, bench_matches);
+criterion_main!(benches);
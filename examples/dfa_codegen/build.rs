@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use automata_rust::{codegen, language::Language, nfa::NFASet, nfa::NFA};
+
+include!("src/rules.rs");
+
+fn main() {
+    let set = NFASet::build(
+        RULES
+            .iter()
+            .map(|&(label, pattern)| (label.into(), NFA::try_from_language(pattern).unwrap()))
+            .collect(),
+    )
+    .unwrap();
+
+    let mut source = codegen::token_matcher("next_token", &set).unwrap();
+    source += &codegen::pattern_matcher("is_number", r"(0-9)+(\.(0-9)+)?").unwrap();
+
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("matchers.rs");
+    std::fs::write(out, source).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/rules.rs");
+}
@@ -0,0 +1,61 @@
+use std::process::ExitCode;
+
+// Generated by `build.rs`, the binary doesn't depend on `automata_rust`.
+include!(concat!(env!("OUT_DIR"), "/matchers.rs"));
+
+fn main() -> ExitCode {
+    let input = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    let mut rest = input.as_str();
+
+    while !rest.is_empty() {
+        let Some((label, size)) = next_token(rest) else {
+            eprintln!("Unrecognized token at {}", input.len() - rest.len());
+            return ExitCode::FAILURE;
+        };
+
+        if label != "space" {
+            println!("{label:>5} {}", &rest[..size]);
+        }
+        rest = &rest[size..];
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use automata_rust::{
+        dfa::DFA,
+        language::{Language, Match},
+        nfa::{NFASet, NFA},
+    };
+
+    use super::*;
+
+    include!("rules.rs");
+
+    #[test]
+    fn same_as_dfa() {
+        let set = NFASet::build(
+            RULES
+                .iter()
+                .map(|&(label, pattern)| (label.into(), NFA::try_from_language(pattern).unwrap()))
+                .collect(),
+        )
+        .unwrap();
+        let dfa = set.determinize();
+
+        for input in ["abc12+3", "12 x", "+-", "\t\n x", "", "!", "a\u{e5}", "Z9*"] {
+            let expected = dfa.longest_match(input).map(|m| match m {
+                Match::Group(label, size) => (label.into(), size),
+                Match::NoGroup(_) => unreachable!(),
+            });
+            assert_eq!(next_token(input), expected, "Input: {input:?}");
+        }
+
+        let dfa = DFA::try_from_language(r"(0-9)+(\.(0-9)+)?").unwrap();
+        for input in ["1", "12.5", "1.", ".5", "", "1.2.3", "x"] {
+            assert_eq!(is_number(input), !dfa.is_match(input).is_empty());
+        }
+    }
+}
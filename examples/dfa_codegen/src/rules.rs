@@ -0,0 +1,8 @@
+/// The token rules, in priority order.
+/// Included by `build.rs` and the tests.
+const RULES: [(&str, &str); 4] = [
+    ("var", r"(a-z|A-Z)(a-z|A-Z|0-9)*"),
+    ("num", r"(0-9)+"),
+    ("op", r"\+|\-|\*|/"),
+    ("space", r"(\ |\t|\n)+"),
+];
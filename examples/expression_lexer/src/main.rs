@@ -1,23 +1,17 @@
 use std::process::ExitCode;
 
 use automata_rust::lexer::prelude::*;
-use lazy_static::lazy_static;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Token)]
 pub enum ExprToken {
+    #[token(r"(a-z|A-z)(a-z|A-Z|0-9)*")]
     Var,
+    #[token(r"\+|\-")]
     Op,
-    Num,
+    #[token(r"(0-9)+", convert = |s| Num(s.parse().unwrap()))]
+    Num(i64),
 }
 
-impl_token!(
-    ExprToken,
-    None,
-    (Var, "var", r"(a-z|A-z)(a-z|A-Z|0-9)*"),
-    (Op, "op", r"\+|\-"),
-    (Num, "num", r"(0-9)+")
-);
-
 fn main() -> ExitCode {
     let text = std::env::args().skip(1);
 
@@ -27,20 +21,17 @@ fn main() -> ExitCode {
         eprintln!("Please provide some input!\nFor example: '2 + 2 - 3'");
         ExitCode::FAILURE
     } else {
-        let lexer = Lexer::<ExprToken>::new(&input);
-        let tokens = lexer.into_iter().collect::<Result<Vec<_>, _>>();
+        let lexer = Lexer::<ExprToken>::new(&input).with_recovery(Recovery::SkipToWhitespace);
+        let (tokens, diagnostics) = lexer.lex_all();
 
-        match tokens {
-            Ok(tokens) => {
-                println!("{:#?}", tokens);
-                ExitCode::SUCCESS
+        if diagnostics.is_empty() {
+            println!("{:#?}", tokens);
+            ExitCode::SUCCESS
+        } else {
+            for diagnostic in diagnostics {
+                eprintln!("{diagnostic}");
             }
-            Err(e) => match e {
-                LexError::UnrecognizedToken(loc) => {
-                    eprintln!("Unrecognized token '{}'", &input[loc..=loc]);
-                    ExitCode::FAILURE
-                }
-            },
+            ExitCode::FAILURE
         }
     }
 }
@@ -0,0 +1,658 @@
+//! Context-free grammars.
+//!
+//! Grammars are written in a simple BNF, the first rule is the start symbol:
+//!
+//! ```text
+//! <expr> ::= <term> "+" <expr> | <term>
+//! <term> ::= "x" | "(" <expr> ")"
+//!          | ""
+//! ```
+//!
+//! Terminals are quoted strings and `""` is the empty string.
+//! Inside a terminal `\n`, `\r` and `\t` are line breaks and tabs, and `\` before any other char is that char.
+//! The input is a sequence of terminals, e.g. the token labels from a lexer.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Symbol {
+    Terminal(String),
+    NonTerminal(String),
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Terminal(t) => write!(f, "\"{}\"", escape(t)),
+            Self::NonTerminal(n) => write!(f, "<{n}>"),
+        }
+    }
+}
+
+/// Escape `"`, `\` and line breaks, the inverse of the escapes read by [`CFG::from_str`].
+fn escape(terminal: &str) -> String {
+    let mut escaped = String::new();
+    for c in terminal.chars() {
+        match c {
+            '"' | '\\' => escaped.extend(['\\', c]),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The rule `head ::= body`, where an empty body derives the empty string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Production {
+    pub head: String,
+    pub body: Vec<Symbol>,
+}
+
+impl Production {
+    #[must_use]
+    pub fn new(head: impl Into<String>, body: Vec<Symbol>) -> Self {
+        Self {
+            head: head.into(),
+            body,
+        }
+    }
+}
+
+impl std::fmt::Display for Production {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}> ::=", self.head)?;
+        if self.body.is_empty() {
+            return write!(f, " \"\"");
+        }
+        for symbol in &self.body {
+            write!(f, " {symbol}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CFGErrorKind {
+    ExpectedNonTerminal,
+    /// Expected `::=` after the head of a rule.
+    ExpectedDefinition,
+    UnterminatedTerminal,
+    UnterminatedNonTerminal,
+    UnexpectedChar(char),
+    /// A non-terminal is used without a rule.
+    Undefined(String),
+    /// The grammar has no rules.
+    Empty,
+}
+
+impl std::fmt::Display for CFGErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ExpectedNonTerminal => write!(f, "Expected a non-terminal"),
+            Self::ExpectedDefinition => write!(f, "Expected '::='"),
+            Self::UnterminatedTerminal => write!(f, "Unterminated terminal"),
+            Self::UnterminatedNonTerminal => write!(f, "Unterminated non-terminal"),
+            Self::UnexpectedChar(c) => write!(f, "Unexpected {c:?}"),
+            Self::Undefined(name) => write!(f, "Undefined non-terminal <{name}>"),
+            Self::Empty => write!(f, "The grammar has no rules"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CFGError {
+    pub kind: CFGErrorKind,
+    /// 1-based line in the grammar.
+    pub line: usize,
+}
+
+impl std::fmt::Display for CFGError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} on line {}", self.kind, self.line)
+    }
+}
+
+impl std::error::Error for CFGError {}
+
+/// A context-free grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CFG {
+    pub start: String,
+    pub productions: Vec<Production>,
+}
+
+impl CFG {
+    /// Convert the grammar into [Chomsky normal form], which derives the same strings.
+    ///
+    /// All rules have the form `<A> ::= <B> <C>` or `<A> ::= "a"`,
+    /// and only the start symbol may derive `""`, in which case it is not used in any body.
+    ///
+    /// [Chomsky normal form]: <https://en.wikipedia.org/wiki/Chomsky_normal_form>
+    #[must_use]
+    pub fn to_cnf(&self) -> CNF {
+        let mut used = self
+            .productions
+            .iter()
+            .map(|p| p.head.clone())
+            .collect::<HashSet<_>>();
+        let mut fresh = |base: &str| {
+            let mut name = base.to_string();
+            while !used.insert(name.clone()) {
+                name.push('\'');
+            }
+            name
+        };
+
+        // A new start symbol which is not used in any body
+        let start = fresh(&self.start);
+        let mut productions = vec![Production::new(
+            &start,
+            vec![Symbol::NonTerminal(self.start.clone())],
+        )];
+
+        // Replace terminals in long bodies, and split the bodies into pairs
+        let mut terminals = HashMap::new();
+        for p in &self.productions {
+            let mut body = p.body.clone();
+            if body.len() >= 2 {
+                for symbol in &mut body {
+                    if let Symbol::Terminal(t) = symbol {
+                        let name = terminals.entry(t.clone()).or_insert_with(|| {
+                            let name = fresh(&format!("'{t}'"));
+                            productions
+                                .push(Production::new(&name, vec![Symbol::Terminal(t.clone())]));
+                            name
+                        });
+                        *symbol = Symbol::NonTerminal(name.clone());
+                    }
+                }
+            }
+
+            let mut head = p.head.clone();
+            while body.len() > 2 {
+                let rest = fresh(&format!("{}_", p.head));
+                let first = body.remove(0);
+                productions.push(Production::new(
+                    head,
+                    vec![first, Symbol::NonTerminal(rest.clone())],
+                ));
+                head = rest;
+            }
+            productions.push(Production::new(head, body));
+        }
+
+        // Remove the empty bodies
+        let nullable = nullable(&productions);
+        let mut without_empty = vec![];
+        for p in productions {
+            let optional = p
+                .body
+                .iter()
+                .map(|s| matches!(s, Symbol::NonTerminal(n) if nullable.contains(n)))
+                .collect::<Vec<_>>();
+
+            // Bodies have at most 2 symbols, so there are at most 4 variants
+            for mask in 0..1usize << p.body.len() {
+                if (0..p.body.len()).any(|i| mask & (1 << i) != 0 && !optional[i]) {
+                    continue;
+                }
+                let body = p
+                    .body
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask & (1 << i) == 0)
+                    .map(|(_, s)| s.clone())
+                    .collect::<Vec<_>>();
+                if !body.is_empty() || p.head == start {
+                    without_empty.push(Production::new(&p.head, body));
+                }
+            }
+        }
+
+        // Replace `<A> ::= <B>` with the bodies of `<B>`
+        let is_unit = |p: &Production| matches!(p.body.as_slice(), [Symbol::NonTerminal(_)]);
+        let mut result = vec![];
+        let mut seen = HashSet::new();
+        for head in without_empty.iter().map(|p| &p.head) {
+            if seen.contains(head) {
+                continue;
+            }
+            seen.insert(head.clone());
+
+            let mut reachable = vec![head.clone()];
+            let mut i = 0;
+            while i < reachable.len() {
+                let units = without_empty
+                    .iter()
+                    .filter_map(|p| match p.body.as_slice() {
+                        [Symbol::NonTerminal(next)] if p.head == reachable[i] => Some(next.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                for next in units {
+                    if !reachable.contains(&next) {
+                        reachable.push(next);
+                    }
+                }
+                i += 1;
+            }
+
+            for p in without_empty
+                .iter()
+                .filter(|p| reachable.contains(&p.head) && !is_unit(p))
+            {
+                let p = Production::new(head, p.body.clone());
+                if !result.contains(&p) {
+                    result.push(p);
+                }
+            }
+        }
+
+        CNF {
+            grammar: Self {
+                start,
+                productions: result,
+            },
+        }
+    }
+}
+
+/// A grammar in [Chomsky normal form], built by [`CFG::to_cnf`].
+///
+/// [Chomsky normal form]: <https://en.wikipedia.org/wiki/Chomsky_normal_form>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CNF {
+    grammar: CFG,
+}
+
+impl CNF {
+    #[must_use]
+    pub fn grammar(&self) -> &CFG {
+        &self.grammar
+    }
+
+    /// Check if the grammar derives `input` using the [CYK algorithm].
+    ///
+    /// [CYK algorithm]: <https://en.wikipedia.org/wiki/CYK_algorithm>
+    #[must_use]
+    pub fn accepts<S: AsRef<str>>(&self, input: &[S]) -> bool {
+        let cnf = &self.grammar;
+
+        if input.is_empty() {
+            return cnf
+                .productions
+                .iter()
+                .any(|p| p.head == cnf.start && p.body.is_empty());
+        }
+
+        // `table[len - 1][i]` contains the non-terminals deriving `input[i..i + len]`
+        let n = input.len();
+        let mut table = vec![vec![HashSet::<&str>::new(); n]; n];
+
+        for (i, token) in input.iter().enumerate() {
+            for p in &cnf.productions {
+                if let [Symbol::Terminal(t)] = p.body.as_slice() {
+                    if t == token.as_ref() {
+                        table[0][i].insert(&p.head);
+                    }
+                }
+            }
+        }
+
+        for len in 2..=n {
+            for i in 0..=n - len {
+                for split in 1..len {
+                    for p in &cnf.productions {
+                        if let [Symbol::NonTerminal(b), Symbol::NonTerminal(c)] = p.body.as_slice()
+                        {
+                            if table[split - 1][i].contains(b.as_str())
+                                && table[len - split - 1][i + split].contains(c.as_str())
+                            {
+                                table[len - 1][i].insert(&p.head);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        table[n - 1][0].contains(cnf.start.as_str())
+    }
+}
+
+/// The non-terminals which derive the empty string.
+fn nullable(productions: &[Production]) -> HashSet<String> {
+    let mut nullable = HashSet::new();
+    loop {
+        let before = nullable.len();
+        for p in productions {
+            if p.body
+                .iter()
+                .all(|s| matches!(s, Symbol::NonTerminal(n) if nullable.contains(n)))
+            {
+                nullable.insert(p.head.clone());
+            }
+        }
+        if nullable.len() == before {
+            return nullable;
+        }
+    }
+}
+
+impl std::fmt::Display for CFG {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut heads = vec![];
+        for p in &self.productions {
+            if !heads.contains(&&p.head) {
+                heads.push(&p.head);
+            }
+        }
+
+        for head in heads {
+            write!(f, "<{head}> ::=")?;
+            for (i, p) in self
+                .productions
+                .iter()
+                .filter(|p| &p.head == head)
+                .enumerate()
+            {
+                if i > 0 {
+                    write!(f, " |")?;
+                }
+                if p.body.is_empty() {
+                    write!(f, " \"\"")?;
+                }
+                for symbol in &p.body {
+                    write!(f, " {symbol}")?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BnfToken {
+    /// A terminal, `None` for `""`.
+    Terminal(Option<String>),
+    NonTerminal(String),
+    Define,
+    Bar,
+}
+
+fn tokenize(line: &str) -> Result<Vec<BnfToken>, CFGErrorKind> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '|' => tokens.push(BnfToken::Bar),
+            ':' if chars.next() == Some(':') && chars.next() == Some('=') => {
+                tokens.push(BnfToken::Define);
+            }
+            '<' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('>') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(CFGErrorKind::UnterminatedNonTerminal),
+                    }
+                }
+                tokens.push(BnfToken::NonTerminal(name));
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some('r') => text.push('\r'),
+                            Some('t') => text.push('\t'),
+                            Some(c) => text.push(c),
+                            None => return Err(CFGErrorKind::UnterminatedTerminal),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err(CFGErrorKind::UnterminatedTerminal),
+                    }
+                }
+                tokens.push(BnfToken::Terminal((!text.is_empty()).then_some(text)));
+            }
+            c => return Err(CFGErrorKind::UnexpectedChar(c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+impl FromStr for CFG {
+    type Err = CFGError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut productions: Vec<(usize, Production)> = vec![];
+
+        for (i, line) in s.lines().enumerate() {
+            let line_number = i + 1;
+            let error = |kind| CFGError {
+                kind,
+                line: line_number,
+            };
+
+            let tokens = tokenize(line).map_err(error)?;
+            let (head, alternatives) = match tokens.as_slice() {
+                [] => continue,
+                // Continues the previous rule
+                [BnfToken::Bar, rest @ ..] => match productions.last() {
+                    Some((_, p)) => (p.head.clone(), rest),
+                    None => return Err(error(CFGErrorKind::ExpectedNonTerminal)),
+                },
+                [BnfToken::NonTerminal(head), BnfToken::Define, rest @ ..] => (head.clone(), rest),
+                [BnfToken::NonTerminal(_), ..] => {
+                    return Err(error(CFGErrorKind::ExpectedDefinition))
+                }
+                _ => return Err(error(CFGErrorKind::ExpectedNonTerminal)),
+            };
+
+            if alternatives.contains(&BnfToken::Define) {
+                return Err(error(CFGErrorKind::UnexpectedChar(':')));
+            }
+
+            for alternative in alternatives.split(|t| *t == BnfToken::Bar) {
+                let body = alternative
+                    .iter()
+                    .filter_map(|t| match t {
+                        BnfToken::Terminal(t) => t.clone().map(Symbol::Terminal),
+                        BnfToken::NonTerminal(n) => Some(Symbol::NonTerminal(n.clone())),
+                        _ => None,
+                    })
+                    .collect();
+                productions.push((line_number, Production::new(&head, body)));
+            }
+        }
+
+        let Some((_, first)) = productions.first() else {
+            return Err(CFGError {
+                kind: CFGErrorKind::Empty,
+                line: 1,
+            });
+        };
+        let start = first.head.clone();
+
+        let heads = productions
+            .iter()
+            .map(|(_, p)| p.head.as_str())
+            .collect::<HashSet<_>>();
+        for (line, p) in &productions {
+            for symbol in &p.body {
+                if let Symbol::NonTerminal(n) = symbol {
+                    if !heads.contains(n.as_str()) {
+                        return Err(CFGError {
+                            kind: CFGErrorKind::Undefined(n.clone()),
+                            line: *line,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            start,
+            productions: productions.into_iter().map(|(_, p)| p).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<String> {
+        input.chars().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse() {
+        let cfg: CFG = r#"
+            <s> ::= "(" <s> ")" <s>
+                  | ""
+            <t> ::= "a\"" <s> | "b"
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(cfg.start, "s");
+        assert_eq!(cfg.productions.len(), 4);
+        assert_eq!(
+            cfg.productions[2],
+            Production::new(
+                "t",
+                vec![
+                    Symbol::Terminal("a\"".into()),
+                    Symbol::NonTerminal("s".into())
+                ]
+            )
+        );
+        assert_eq!(
+            cfg.to_string(),
+            "<s> ::= \"(\" <s> \")\" <s> | \"\"\n<t> ::= \"a\\\"\" <s> | \"b\"\n"
+        );
+        assert_eq!(cfg.to_string().parse::<CFG>(), Ok(cfg));
+
+        let escaped = CFG {
+            start: "s".into(),
+            productions: vec![Production::new(
+                "s",
+                vec![
+                    Symbol::Terminal("a\nb".into()),
+                    Symbol::Terminal("\t\\\"".into()),
+                ],
+            )],
+        };
+        assert_eq!(escaped.to_string(), "<s> ::= \"a\\nb\" \"\\t\\\\\\\"\"\n");
+        assert_eq!(escaped.to_string().parse::<CFG>(), Ok(escaped));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |s: &str| s.parse::<CFG>().unwrap_err();
+
+        assert_eq!(error("").kind, CFGErrorKind::Empty);
+        assert_eq!(
+            error("<a> ::= \"x\"\n<b> \"y\""),
+            CFGError {
+                kind: CFGErrorKind::ExpectedDefinition,
+                line: 2
+            }
+        );
+        assert_eq!(
+            error("\"x\" ::= <a>").kind,
+            CFGErrorKind::ExpectedNonTerminal
+        );
+        assert_eq!(error("| \"x\"").kind, CFGErrorKind::ExpectedNonTerminal);
+        assert_eq!(
+            error("<a> ::= \"x").kind,
+            CFGErrorKind::UnterminatedTerminal
+        );
+        assert_eq!(
+            error("<a ::= \"x\"").kind,
+            CFGErrorKind::UnterminatedNonTerminal
+        );
+        assert_eq!(error("<a> ::= x").kind, CFGErrorKind::UnexpectedChar('x'));
+        assert_eq!(
+            error("<a> ::= \"x\"\n\n<b> ::= <c>"),
+            CFGError {
+                kind: CFGErrorKind::Undefined("c".into()),
+                line: 3
+            }
+        );
+    }
+
+    #[test]
+    fn accepts() {
+        let balanced = r#"<s> ::= "(" <s> ")" <s> | """#.parse::<CFG>().unwrap().to_cnf();
+        for input in ["", "()", "(())()", "((()())())"] {
+            assert!(balanced.accepts(&tokens(input)), "Input: {input}");
+        }
+        for input in ["(", ")(", "(()", "())", "x"] {
+            assert!(!balanced.accepts(&tokens(input)), "Input: {input}");
+        }
+
+        // Left recursive with unit rules
+        let expr = r#"
+            <expr> ::= <expr> "+" <term> | <term>
+            <term> ::= <term> "*" <factor> | <factor>
+            <factor> ::= "x" | "(" <expr> ")"
+        "#
+        .parse::<CFG>()
+        .unwrap()
+        .to_cnf();
+        for input in ["x", "x+x", "x*(x+x)*x", "((x))"] {
+            assert!(expr.accepts(&tokens(input)), "Input: {input}");
+        }
+        for input in ["", "+", "x+", "x(x)", "(x"] {
+            assert!(!expr.accepts(&tokens(input)), "Input: {input}");
+        }
+
+        // Terminals with more than one char
+        let keywords =
+            r#"<s> ::= "if" "cond" "then" <s> | "stmt""#.parse::<CFG>().unwrap().to_cnf();
+        assert!(keywords.accepts(&["if", "cond", "then", "stmt"]));
+        assert!(!keywords.accepts(&["if", "cond", "stmt"]));
+    }
+
+    #[test]
+    fn cnf() {
+        let cfg: CFG = r#"
+            <s> ::= <a> "b" <a> <s> | <a>
+            <a> ::= "a" | ""
+        "#
+        .parse()
+        .unwrap();
+
+        let cnf = cfg.to_cnf();
+        let start = &cnf.grammar().start;
+        for p in &cnf.grammar().productions {
+            match p.body.as_slice() {
+                [Symbol::NonTerminal(b), Symbol::NonTerminal(c)] => {
+                    assert!(b != start && c != start, "{p}");
+                }
+                [Symbol::Terminal(_)] => {}
+                [] => assert_eq!(&p.head, start),
+                _ => panic!("Not in CNF: {p}"),
+            }
+        }
+
+        assert!(cnf.accepts(&tokens("")));
+        assert!(cnf.accepts(&tokens("abab")));
+        assert!(cnf.accepts(&tokens("bb")));
+        assert!(!cnf.accepts(&tokens("aa")));
+    }
+}
@@ -0,0 +1,233 @@
+//! Generate Rust source for a DFA, e.g. from a `build.rs` script.
+//!
+//! The generated functions are a `match` over the current state and the next char,
+//! they don't depend on this crate.
+//!
+//! ```ignore
+//! // build.rs
+//! let source = automata_rust::codegen::pattern_matcher("is_number", "(0-9)+").unwrap();
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("matchers.rs");
+//! std::fs::write(out, source).unwrap();
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/matchers.rs"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::{
+    dfa::{TokenDFA, DFA},
+    language::{Label, Language, LanguageError},
+    nfa::{NFASet, State},
+};
+
+/// Generate `pub fn {name}(input: &str) -> bool`, which is true if all of `input` matches `pattern`.
+///
+/// # Errors
+///
+/// Fails if the pattern is invalid or the DFA needs more than [`DFA::DEFAULT_MAX_STATES`] states.
+pub fn pattern_matcher(name: &str, pattern: &str) -> Result<String, LanguageError> {
+    let dfa = DFA::try_from_language(pattern)?;
+    Ok(dfa_matcher(name, &dfa.minimize()))
+}
+
+/// Generate `pub fn {name}(input: &str) -> Option<(&'static str, usize)>`,
+/// which returns the label and length of the longest match at the start of `input`,
+/// the same as [`NFASet::longest_match`].
+///
+/// # Errors
+///
+/// Fails if the DFA needs more than [`DFA::DEFAULT_MAX_STATES`] states.
+pub fn token_matcher(name: &str, set: &NFASet) -> Result<String, LanguageError> {
+    let dfa = TokenDFA::build(set, DFA::DEFAULT_MAX_STATES)?;
+    Ok(token_dfa_matcher(name, &dfa.minimize()))
+}
+
+/// Same as [`pattern_matcher`] for a DFA which has already been built.
+#[must_use]
+pub fn dfa_matcher(name: &str, dfa: &DFA) -> String {
+    let mut accept = dfa.accept.iter().map(|s| s.0).collect::<Vec<_>>();
+    accept.sort_unstable();
+    let accept = if accept.is_empty() {
+        "false".to_string()
+    } else {
+        format!("matches!(state, {})", join(accept, " | "))
+    };
+
+    format!(
+        "#[allow(unused, clippy::all)]
+pub fn {name}(input: &str) -> bool {{
+    let mut state = {start}usize;
+    for c in input.chars() {{
+        state = match (state, c) {{
+{arms}            _ => return false,
+        }};
+    }}
+    {accept}
+}}
+",
+        start = dfa.start.0,
+        arms = transition_arms(&dfa.alphabet, &dfa.transitions),
+    )
+}
+
+/// Same as [`token_matcher`] for a DFA which has already been built.
+#[must_use]
+pub fn token_dfa_matcher(name: &str, dfa: &TokenDFA) -> String {
+    let start = match dfa.accept[dfa.start] {
+        Some(label) => format!("Some(({:?}, 0))", <&str>::from(label)),
+        None => "None".to_string(),
+    };
+
+    format!(
+        "#[allow(unused, clippy::all)]
+pub fn {name}(input: &str) -> Option<(&'static str, usize)> {{
+    let mut state = {start_state}usize;
+    let mut last = {start};
+    for (i, c) in input.char_indices() {{
+        state = match (state, c) {{
+{arms}            _ => return last,
+        }};
+        match state {{
+{accept}            _ => {{}}
+        }}
+    }}
+    match state {{
+{accept_eof}        _ => last,
+    }}
+}}
+",
+        start_state = dfa.start.0,
+        arms = transition_arms(&dfa.alphabet, &dfa.transitions),
+        accept = label_arms(
+            &dfa.accept,
+            "            ",
+            "last = Some((",
+            ", i + c.len_utf8()))"
+        ),
+        accept_eof = label_arms(&dfa.accept_eof, "        ", "Some((", ", input.len()))"),
+    )
+}
+
+/// One arm for each state and target, `(state, 'a'..='z' | '_') => next,`.
+fn transition_arms(
+    alphabet: &[RangeInclusive<char>],
+    transitions: &[Vec<Option<State>>],
+) -> String {
+    let mut arms = String::new();
+
+    for (state, row) in transitions.iter().enumerate() {
+        let mut targets = BTreeMap::<usize, Vec<RangeInclusive<char>>>::new();
+        for (class, next) in alphabet.iter().zip(row) {
+            let Some(next) = next else {
+                continue;
+            };
+
+            let ranges = targets.entry(next.0).or_default();
+            match ranges.last_mut() {
+                // Merge with the previous class if they are adjacent
+                Some(last) if *last.end() as u32 + 1 == *class.start() as u32 => {
+                    *last = *last.start()..=*class.end();
+                }
+                _ => ranges.push(class.clone()),
+            }
+        }
+
+        for (next, ranges) in targets {
+            let pattern = join(
+                ranges.iter().map(|range| {
+                    if range.start() == range.end() {
+                        format!("{:?}", range.start())
+                    } else {
+                        format!("{:?}..={:?}", range.start(), range.end())
+                    }
+                }),
+                " | ",
+            );
+            arms += &format!("            ({state}, {pattern}) => {next},\n");
+        }
+    }
+
+    arms
+}
+
+/// One arm for each label, `1 | 3 => {prefix}"label"{suffix},`.
+fn label_arms(labels: &[Option<Label>], indent: &str, prefix: &str, suffix: &str) -> String {
+    let mut states = BTreeMap::<&str, Vec<usize>>::new();
+    for (state, label) in labels.iter().enumerate() {
+        if let Some(label) = label {
+            states.entry((*label).into()).or_default().push(state);
+        }
+    }
+
+    states
+        .into_iter()
+        .map(|(label, states)| {
+            format!(
+                "{indent}{} => {prefix}{label:?}{suffix},\n",
+                join(states, " | ")
+            )
+        })
+        .collect()
+}
+
+fn join<T: ToString>(items: impl IntoIterator<Item = T>, sep: &str) -> String {
+    items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern() {
+        let source = pattern_matcher("is_ident", "(a-z|_)(a-z|_|0-9)*").unwrap();
+        assert_eq!(
+            source,
+            "#[allow(unused, clippy::all)]
+pub fn is_ident(input: &str) -> bool {
+    let mut state = 0usize;
+    for c in input.chars() {
+        state = match (state, c) {
+            (0, '_' | 'a'..='z') => 1,
+            (1, '0'..='9' | '_' | 'a'..='z') => 1,
+            _ => return false,
+        };
+    }
+    matches!(state, 1)
+}
+"
+        );
+
+        assert!(pattern_matcher("f", "(a").is_err());
+    }
+
+    #[test]
+    fn token() {
+        use crate::nfa::NFA;
+
+        let set = NFASet::build(vec![
+            ("if".into(), NFA::try_from_language("if").unwrap()),
+            ("ident".into(), NFA::try_from_language("(a-z)+").unwrap()),
+            ("end".into(), NFA::try_from_language("\\.$").unwrap()),
+        ])
+        .unwrap();
+        let source = token_matcher("next_token", &set).unwrap();
+
+        assert!(source.starts_with(
+            "#[allow(unused, clippy::all)]
+pub fn next_token(input: &str) -> Option<(&'static str, usize)> {
+    let mut state = 0usize;
+    let mut last = None;
+"
+        ));
+        assert!(source.contains("=> last = Some((\"if\", i + c.len_utf8())),"));
+        assert!(source.contains("=> Some((\"end\", input.len())),"));
+        assert!(source.contains("'.') =>"));
+    }
+}
@@ -0,0 +1,9 @@
+//! Hash maps from `std` when available, otherwise from `hashbrown`.
+
+#[cfg(feature = "std")]
+pub use std::collections::{HashMap, HashSet};
+
+#[cfg(not(feature = "std"))]
+pub type HashMap<K, V> = hashbrown::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub type HashSet<T> = hashbrown::HashSet<T>;
@@ -1,20 +1,183 @@
-use std::collections::{HashMap, HashSet};
+use alloc::collections::VecDeque;
+use alloc::{string::String, vec, vec::Vec};
+use core::ops::RangeInclusive;
 
 use crate::{
+    collections::{HashMap, HashSet},
     language::{Language, LanguageError, Match},
-    nfa::{State, NFA},
+    nfa::{State, Transition, NFA},
 };
 
+use super::{
+    minimize::{minimize, partition},
+    powerset::{class_of, Powerset},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeterminizeError {
+    /// The subset construction needed more states than the limit `max_states`.
+    TooLarge { max_states: usize },
+}
+
+impl core::fmt::Display for DeterminizeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooLarge { max_states } => {
+                write!(f, "The DFA needs more states than the limit of {max_states}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for DeterminizeError {}
+
+#[derive(Debug)]
 pub struct DFA {
-    pub alphabet: Vec<char>,
-    pub transitions: Vec<HashMap<char, State>>,
+    /// Disjoint character classes in ascending order.
+    /// Every char in a class has the same transitions.
+    pub alphabet: Vec<RangeInclusive<char>>,
+    /// `transitions[state][class]` is the state reached after reading a char in `alphabet[class]`.
+    /// The input is rejected when the transition is `None`.
+    pub transitions: Vec<Vec<Option<State>>>,
     pub start: State,
     pub accept: HashSet<State>,
 }
 
+impl DFA {
+    /// Maximum number of states used by [`DFA::try_from_language`].
+    pub const DEFAULT_MAX_STATES: usize = 10_000;
+
+    /// Convert `nfa` into a DFA using the [Powerset construction].
+    ///
+    /// [Powerset construction]: <https://en.wikipedia.org/wiki/Powerset_construction>
+    ///
+    /// # Errors
+    ///
+    /// Fails if the DFA needs more than `max_states` states.
+    /// The number of states can grow exponentially, e.g. for `(a|b)*a(a|b)(a|b)(a|b)`.
+    pub fn determinize(nfa: &NFA, max_states: usize) -> Result<Self, DeterminizeError> {
+        let powerset = Powerset::build(nfa, max_states, false)?;
+
+        let accept = powerset
+            .sets
+            .iter()
+            .enumerate()
+            .filter(|(_, set)| {
+                set.iter()
+                    .any(|&(_, s)| matches!(nfa[s], Transition::Accept | Transition::Eof))
+            })
+            .map(|(state, _)| State(state))
+            .collect();
+
+        Ok(Self {
+            alphabet: powerset.alphabet,
+            transitions: powerset.transitions,
+            start: State(0),
+            accept,
+        })
+    }
+
+    /// Merge equivalent states and remove the states which can't reach an accepting state.
+    /// The result is the DFA with the fewest states accepting the same language.
+    #[must_use]
+    pub fn minimize(&self) -> Self {
+        let minimized = minimize(self.start, &self.transitions, |s| {
+            self.accept.contains(&s).then_some(())
+        });
+
+        let accept = minimized
+            .representatives
+            .iter()
+            .enumerate()
+            .filter(|(_, &s)| self.accept.contains(&s))
+            .map(|(state, _)| State(state))
+            .collect();
+
+        Self {
+            alphabet: self.alphabet.clone(),
+            transitions: minimized.transitions,
+            start: State(0),
+            accept,
+        }
+    }
+
+    /// Group the states which accept the same strings, the [Myhill-Nerode] equivalence classes.
+    /// The states which can't reach an accepting state are in the same class.
+    ///
+    /// [`DFA::minimize`] merges the states in each class into a single state.
+    ///
+    /// [Myhill-Nerode]: <https://en.wikipedia.org/wiki/Myhill%E2%80%93Nerode_theorem>
+    #[must_use]
+    pub fn equivalence_classes(&self) -> Vec<Vec<State>> {
+        let partition = partition(&self.transitions, |s| {
+            self.accept.contains(&s).then_some(())
+        });
+
+        let mut ids = HashMap::new();
+        let mut classes: Vec<Vec<State>> = vec![];
+        for (state, class) in partition.into_iter().enumerate() {
+            let id = *ids.entry(class).or_insert_with(|| {
+                classes.push(vec![]);
+                classes.len() - 1
+            });
+            classes[id].push(State(state));
+        }
+
+        classes
+    }
+
+    /// Find the shortest string which is accepted when starting in one of the states but not the other.
+    /// Returns `None` if the states are equivalent.
+    #[must_use]
+    pub fn distinguishing_string(&self, s1: State, s2: State) -> Option<String> {
+        // `None` is the implicit state reached when there is no transition
+        let accepts = |s: Option<State>| s.is_some_and(|s| self.accept.contains(&s));
+        let start = (Some(s1), Some(s2));
+
+        // The previous pair and the char read for each visited pair
+        let mut parents = HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(pair) = queue.pop_front() {
+            if accepts(pair.0) != accepts(pair.1) {
+                let mut chars = vec![];
+                let mut current = pair;
+                while let Some(&Some((prev, c))) = parents.get(&current) {
+                    chars.push(c);
+                    current = prev;
+                }
+                return Some(chars.into_iter().rev().collect());
+            }
+
+            for (class, range) in self.alphabet.iter().enumerate() {
+                let step = |s: Option<State>| s.and_then(|s| self.transitions[s.0][class]);
+                let next = (step(pair.0), step(pair.1));
+                if next == (None, None) || parents.contains_key(&next) {
+                    continue;
+                }
+                parents.insert(next, Some((pair, *range.start())));
+                queue.push_back(next);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the index of the class in `alphabet` which contains `c`.
+    #[must_use]
+    pub fn class_of(&self, c: char) -> Option<usize> {
+        class_of(&self.alphabet, c)
+    }
+}
+
 impl From<NFA> for DFA {
-    fn from(_: NFA) -> Self {
-        todo!()
+    /// Convert without any limit on the number of states.
+    /// Use [`DFA::determinize`] for untrusted patterns.
+    fn from(nfa: NFA) -> Self {
+        match Self::determinize(&nfa, usize::MAX) {
+            Ok(dfa) => dfa,
+            Err(e) => unreachable!("{e}"),
+        }
     }
 }
 
@@ -22,9 +185,12 @@ impl Language for DFA {
     fn is_match(&self, input: &str) -> Vec<Match> {
         let mut current = self.start;
         for c in input.chars() {
-            match self.transitions[current].get(&c) {
-                Some(next) => current = *next,
-                None => panic!("Transition table does not contain char: {c}"),
+            match self
+                .class_of(c)
+                .and_then(|class| self.transitions[current][class])
+            {
+                Some(next) => current = next,
+                None => return vec![],
             }
         }
 
@@ -40,30 +206,32 @@ impl Language for DFA {
     }
 
     fn try_from_language<S: AsRef<str>>(source: S) -> Result<Self, LanguageError> {
-        NFA::try_from_language(source).map(Self::from)
+        let nfa = NFA::try_from_language(source)?;
+        Ok(Self::determinize(&nfa, Self::DEFAULT_MAX_STATES)?)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::graph_display::DiGraph;
-
     use super::*;
 
     #[test]
+    #[cfg(feature = "std")]
     fn dfa() {
+        use crate::graph_display::DiGraph;
+
         let dfa = DFA {
-            alphabet: vec!['0', '1'],
+            alphabet: vec!['0'..='0', '1'..='1'],
             transitions: vec![
-                HashMap::from([('0', State(1)), ('1', State(0))]),
-                HashMap::from([('0', State(0)), ('1', State(1))]),
+                vec![Some(State(1)), Some(State(0))],
+                vec![Some(State(0)), Some(State(1))],
             ],
             start: State(0),
             accept: HashSet::from([State(0)]),
         };
 
         let graph: DiGraph = (&dfa).into();
-        std::fs::write("./graph.svg", graph.to_string()).expect("Could not write data to file!");
+        std::fs::write("./graph.svg", graph.render().unwrap()).expect("Could not write data to file!");
 
         println!("{:?}", dfa.is_match("01"));
 
@@ -73,4 +241,137 @@ mod tests {
         assert!(!dfa.is_match("010").is_empty());
         assert!(!dfa.is_match("00111010").is_empty());
     }
+
+    #[test]
+    fn determinize() {
+        let dfa = DFA::try_from_language("(0|1)*1").unwrap();
+        assert_eq!(dfa.is_match("1"), vec![Match::NoGroup(1)]);
+        assert_eq!(dfa.is_match("0101"), vec![Match::NoGroup(4)]);
+        assert!(dfa.is_match("").is_empty());
+        assert!(dfa.is_match("10").is_empty());
+        assert!(dfa.is_match("012").is_empty());
+
+        let dfa = DFA::try_from_language("(a-z)+(0-9|\\-)?").unwrap();
+        assert_eq!(dfa.alphabet, vec!['-'..='-', '0'..='9', 'a'..='z']);
+        assert_eq!(dfa.is_match("abc-"), vec![Match::NoGroup(4)]);
+        assert_eq!(dfa.is_match("abcd"), vec![Match::NoGroup(4)]);
+        assert_eq!(dfa.is_match("ab7"), vec![Match::NoGroup(3)]);
+        assert!(dfa.is_match("ab\u{1F600}").is_empty());
+        assert!(dfa.is_match("a77").is_empty());
+
+        let dfa = DFA::try_from_language("ab$|a").unwrap();
+        assert_eq!(dfa.is_match("ab"), vec![Match::NoGroup(2)]);
+        assert_eq!(dfa.is_match("a"), vec![Match::NoGroup(1)]);
+        assert!(dfa.is_match("abb").is_empty());
+
+        // Lazy quantifiers accept the same inputs as greedy ones
+        for (pattern, input) in [("a+?", "aaa"), ("(a|b)*?b", "abab"), ("(ab)*?", "abab")] {
+            let dfa = DFA::try_from_language(pattern).unwrap();
+            let size = input.len();
+            assert_eq!(dfa.is_match(input), vec![Match::NoGroup(size)], "{pattern}");
+            assert_eq!(
+                dfa.minimize().is_match(input),
+                vec![Match::NoGroup(size)],
+                "{pattern}"
+            );
+        }
+    }
+
+    #[test]
+    fn minimize() {
+        for (pattern, states) in [
+            ("(0|1)*1", 2),
+            ("(a|b)*abb", 4),
+            ("ab|cb", 3),
+            ("(a-z)+(0-9|\\-)?", 3),
+            ("ab$|a", 3),
+        ] {
+            let dfa = DFA::try_from_language(pattern).unwrap();
+            let minimized = dfa.minimize();
+            assert_eq!(minimized.transitions.len(), states, "Pattern: {pattern}");
+
+            for input in [
+                "", "1", "0101", "10", "abb", "babb", "ab", "cb", "a", "ab-", "ab7x",
+            ] {
+                assert_eq!(
+                    minimized.is_match(input),
+                    dfa.is_match(input),
+                    "Pattern: {pattern}, input: {input}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn distinguish() {
+        // Ends with "ab", with redundant states
+        let row = |a, b| vec![Some(State(a)), Some(State(b))];
+        let dfa = DFA {
+            alphabet: vec!['a'..='a', 'b'..='b'],
+            transitions: vec![row(1, 0), row(1, 2), row(3, 0), row(3, 4), row(1, 0)],
+            start: State(0),
+            accept: HashSet::from([State(2), State(4)]),
+        };
+
+        assert_eq!(
+            dfa.equivalence_classes(),
+            vec![
+                vec![State(0)],
+                vec![State(1), State(3)],
+                vec![State(2), State(4)]
+            ]
+        );
+
+        assert_eq!(dfa.distinguishing_string(State(1), State(3)), None);
+        assert_eq!(dfa.distinguishing_string(State(2), State(2)), None);
+        assert_eq!(
+            dfa.distinguishing_string(State(0), State(2)),
+            Some(String::new())
+        );
+        assert_eq!(
+            dfa.distinguishing_string(State(0), State(1)),
+            Some("b".to_string())
+        );
+        assert_eq!(
+            dfa.distinguishing_string(State(0), State(3)),
+            Some("b".to_string())
+        );
+
+        // Missing transitions behave like a state which never accepts
+        let dfa = DFA::try_from_language("ab|cd|e").unwrap();
+        let classes = dfa.equivalence_classes();
+        assert_eq!(classes.len(), dfa.minimize().transitions.len());
+        for class in &classes {
+            for &s in class {
+                assert_eq!(dfa.distinguishing_string(class[0], s), None);
+            }
+        }
+        let after = |c| dfa.transitions[dfa.start.0][dfa.class_of(c).unwrap()].unwrap();
+        assert_eq!(
+            dfa.distinguishing_string(after('a'), after('c')),
+            Some("b".to_string())
+        );
+        assert_eq!(
+            dfa.distinguishing_string(after('a'), after('e')),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn max_states() {
+        let pattern = "(a|b)*a".to_string() + &"(a|b)".repeat(10);
+        let nfa = NFA::try_from_language(&pattern).unwrap();
+
+        assert_eq!(
+            DFA::determinize(&nfa, 100).unwrap_err(),
+            DeterminizeError::TooLarge { max_states: 100 }
+        );
+
+        let dfa = DFA::determinize(&nfa, DFA::DEFAULT_MAX_STATES).unwrap();
+        assert_eq!(dfa.transitions.len(), 1 << 11);
+        assert!(!dfa
+            .is_match(&("a".to_string() + &"b".repeat(10)))
+            .is_empty());
+        assert!(dfa.is_match(&"b".repeat(11)).is_empty());
+    }
 }
@@ -0,0 +1,181 @@
+//! Minimization by partition refinement, shared by [`super::DFA`] and [`super::TokenDFA`].
+
+use alloc::{vec, vec::Vec};
+use core::hash::Hash;
+
+use crate::{collections::HashMap, nfa::State};
+
+#[derive(Debug)]
+pub(crate) struct Minimized {
+    /// The start state is always `State(0)`.
+    pub transitions: Vec<Vec<Option<State>>>,
+    /// A state in the original DFA for each state in the minimized DFA.
+    pub representatives: Vec<State>,
+}
+
+/// Merge equivalent states and remove the states which can't reach an accepting state.
+/// A state is accepting if `output` is not `None`.
+pub(crate) fn minimize<K: Eq + Hash>(
+    start: State,
+    transitions: &[Vec<Option<State>>],
+    output: impl Fn(State) -> Option<K>,
+) -> Minimized {
+    let class = partition(transitions, output);
+
+    let Some(start_class) = class[start.0] else {
+        // Nothing is accepted
+        return Minimized {
+            transitions: vec![vec![None; transitions[start.0].len()]],
+            representatives: vec![start],
+        };
+    };
+
+    let mut members = HashMap::new();
+    for (state, class) in class.iter().enumerate() {
+        if let Some(class) = class {
+            members.entry(*class).or_insert(State(state));
+        }
+    }
+
+    // Number the states in the order they are reached from the start state
+    let mut ids = HashMap::from([(start_class, State(0))]);
+    let mut representatives = vec![start];
+    let mut minimized = vec![];
+
+    while minimized.len() < representatives.len() {
+        let representative = representatives[minimized.len()];
+        let row = transitions[representative.0]
+            .iter()
+            .map(|next| {
+                let next = class[next.as_ref()?.0]?;
+                Some(*ids.entry(next).or_insert_with(|| {
+                    representatives.push(members[&next]);
+                    State(representatives.len() - 1)
+                }))
+            })
+            .collect();
+        minimized.push(row);
+    }
+
+    Minimized {
+        transitions: minimized,
+        representatives,
+    }
+}
+
+/// Group the equivalent states, `None` for states which can't reach an accepting state.
+/// Two states are equivalent if they have the same `output`
+/// and their transitions lead to equivalent states.
+pub(crate) fn partition<K: Eq + Hash>(
+    transitions: &[Vec<Option<State>>],
+    output: impl Fn(State) -> Option<K>,
+) -> Vec<Option<usize>> {
+    let outputs = (0..transitions.len())
+        .map(|state| output(State(state)))
+        .collect::<Vec<_>>();
+    let live = live_states(transitions, &outputs);
+
+    let mut ids = HashMap::new();
+    let mut class = outputs
+        .iter()
+        .zip(live)
+        .map(|(output, live)| {
+            live.then(|| {
+                let id = ids.len();
+                *ids.entry(output).or_insert(id)
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut count = ids.len();
+
+    // Split the classes until the transitions of all states in a class agree
+    loop {
+        let mut ids = HashMap::new();
+        let refined = class
+            .iter()
+            .zip(transitions)
+            .map(|(current, row)| {
+                let row = row
+                    .iter()
+                    .map(|next| class[next.as_ref()?.0])
+                    .collect::<Vec<_>>();
+                current.map(|current| {
+                    let id = ids.len();
+                    *ids.entry((current, row)).or_insert(id)
+                })
+            })
+            .collect();
+
+        class = refined;
+        if ids.len() == count {
+            return class;
+        }
+        count = ids.len();
+    }
+}
+
+/// States from which an accepting state can be reached.
+fn live_states<K>(transitions: &[Vec<Option<State>>], outputs: &[Option<K>]) -> Vec<bool> {
+    let mut reverse = vec![vec![]; transitions.len()];
+    for (state, row) in transitions.iter().enumerate() {
+        for next in row.iter().flatten() {
+            reverse[next.0].push(state);
+        }
+    }
+
+    let mut stack = (0..transitions.len())
+        .filter(|&state| outputs[state].is_some())
+        .collect::<Vec<_>>();
+    let mut live = vec![false; transitions.len()];
+    for &state in &stack {
+        live[state] = true;
+    }
+
+    while let Some(state) = stack.pop() {
+        for &prev in &reverse[state] {
+            if !live[prev] {
+                live[prev] = true;
+                stack.push(prev);
+            }
+        }
+    }
+
+    live
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimize_states() {
+        // 0 -> 1 and 0 -> 2 are equivalent, 3 never accepts and 4 is unreachable
+        let transitions = vec![
+            vec![Some(State(1)), Some(State(2)), Some(State(3))],
+            vec![Some(State(1)), None, None],
+            vec![Some(State(2)), None, None],
+            vec![Some(State(3)), None, None],
+            vec![None, None, None],
+        ];
+        let accept = [false, true, true, false, true];
+        let output = |s: State| accept[s.0].then_some(());
+
+        assert_eq!(
+            partition(&transitions, output),
+            vec![Some(0), Some(1), Some(1), None, Some(2)]
+        );
+
+        let minimized = minimize(State(0), &transitions, output);
+        assert_eq!(
+            minimized.transitions,
+            vec![
+                vec![Some(State(1)), Some(State(1)), None],
+                vec![Some(State(1)), None, None],
+            ]
+        );
+        assert_eq!(minimized.representatives, vec![State(0), State(1)]);
+
+        let minimized = minimize(State(3), &transitions, output);
+        assert_eq!(minimized.transitions, vec![vec![None; 3]]);
+    }
+}
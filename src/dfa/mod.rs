@@ -1,3 +1,10 @@
+#[allow(clippy::module_inception)]
 mod dfa;
+mod minimize;
+mod powerset;
+mod token_dfa;
 
-pub use dfa::DFA;
+pub use dfa::{DeterminizeError, DFA};
+pub use token_dfa::{PartialMatch, Scan, TokenDFA};
+
+pub(crate) use powerset::lazy_closure;
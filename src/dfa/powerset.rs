@@ -0,0 +1,266 @@
+//! The [Powerset construction] shared by [`super::DFA`] and [`super::TokenDFA`].
+//!
+//! [Powerset construction]: <https://en.wikipedia.org/wiki/Powerset_construction>
+
+use alloc::collections::BTreeSet;
+use alloc::{vec, vec::Vec};
+use core::ops::RangeInclusive;
+
+use crate::{
+    collections::HashMap,
+    language::Label,
+    nfa::{State, Transition, NFA},
+    parse::Lit,
+};
+
+use super::DeterminizeError;
+
+/// A NFA state together with the group it was reached from.
+pub(crate) type GroupState = (Option<Label>, State);
+
+#[derive(Debug)]
+pub(crate) struct Powerset {
+    /// Disjoint character classes in ascending order.
+    pub alphabet: Vec<RangeInclusive<char>>,
+    /// `transitions[state][class]` is the state reached after reading a char in `alphabet[class]`.
+    pub transitions: Vec<Vec<Option<State>>>,
+    /// The NFA states contained in each DFA state.
+    /// The start state is always `State(0)`.
+    pub sets: Vec<Vec<GroupState>>,
+}
+
+impl Powerset {
+    /// If `lazy`, lazy quantifiers stop once their group has matched, see [`stop_lazy`].
+    /// Only for longest matches, they don't change which inputs are accepted as a whole.
+    pub fn build(nfa: &NFA, max_states: usize, lazy: bool) -> Result<Self, DeterminizeError> {
+        if max_states == 0 {
+            return Err(DeterminizeError::TooLarge { max_states });
+        }
+
+        let alphabet = char_classes(nfa.transitions.iter().filter_map(|t| match t {
+            Transition::Label(l, _) => Some(l),
+            _ => None,
+        }));
+
+        let follow = |states: Vec<GroupState>| {
+            if lazy {
+                stop_lazy(nfa, lazy_closure(nfa, states))
+            } else {
+                closure(nfa, states)
+            }
+        };
+
+        let start = follow(vec![(None, nfa.start)]);
+        let mut ids = HashMap::from([(start.clone(), State(0))]);
+        let mut sets = vec![start];
+        let mut transitions = vec![];
+
+        // `sets` grows while we iterate over it
+        let mut current = 0;
+        while current < sets.len() {
+            let mut row = Vec::with_capacity(alphabet.len());
+            for class in &alphabet {
+                let c = *class.start();
+                let next = follow(
+                    sets[current]
+                        .iter()
+                        .filter_map(|&(group, s)| match &nfa[s] {
+                            Transition::Label(l, e) if l.accepts(c) => Some((group, *e)),
+                            _ => None,
+                        })
+                        .collect(),
+                );
+
+                if next.is_empty() {
+                    row.push(None);
+                    continue;
+                }
+
+                let state = if let Some(state) = ids.get(&next) {
+                    *state
+                } else {
+                    if sets.len() >= max_states {
+                        return Err(DeterminizeError::TooLarge { max_states });
+                    }
+                    let state = State(sets.len());
+                    ids.insert(next.clone(), state);
+                    sets.push(next);
+                    state
+                };
+                row.push(Some(state));
+            }
+
+            transitions.push(row);
+            current += 1;
+        }
+
+        Ok(Self {
+            alphabet,
+            transitions,
+            sets,
+        })
+    }
+}
+
+/// Returns the index of the class in `alphabet` which contains `c`.
+pub(crate) fn class_of(alphabet: &[RangeInclusive<char>], c: char) -> Option<usize> {
+    alphabet
+        .binary_search_by(|class| {
+            if *class.end() < c {
+                core::cmp::Ordering::Less
+            } else if *class.start() > c {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .ok()
+}
+
+/// Follow all eps-transitions from `states`.
+/// Returns the reached states which consume input, accept or mark the end of input.
+fn closure(nfa: &NFA, states: Vec<GroupState>) -> Vec<GroupState> {
+    let mut set = lazy_closure(nfa, states)
+        .into_iter()
+        .map(|(state, _)| state)
+        .collect::<Vec<_>>();
+    set.dedup();
+    set
+}
+
+/// Same as [`closure`], each state is paired with `true` if it is only reached
+/// through the lazy branch of a split in [`NFA::lazy`].
+pub(crate) fn lazy_closure(nfa: &NFA, states: Vec<GroupState>) -> Vec<(GroupState, bool)> {
+    let mut visited = vec![false; nfa.transitions.len()];
+    let mut stack = states.into_iter().map(|s| (s, false)).collect::<Vec<_>>();
+    // Lazy branches are followed last, so the states also reached otherwise are not lazy
+    let mut lazy = vec![];
+    let mut set = vec![];
+
+    while let Some(((group, state), is_lazy)) = stack.pop().or_else(|| Some((lazy.pop()?, true))) {
+        // The accept state is shared by all groups in an `NFASet`
+        if state == nfa.accept {
+            set.push(((group, state), is_lazy));
+            continue;
+        }
+
+        if visited[state.0] {
+            continue;
+        }
+        visited[state.0] = true;
+
+        match &nfa[state] {
+            &Transition::Split(e1, e2) => {
+                if nfa.lazy.contains(&state) && !is_lazy {
+                    lazy.extend(e1.map(|e| (group, e)));
+                } else {
+                    stack.extend(e1.map(|e| ((group, e), is_lazy)));
+                }
+                stack.extend(e2.map(|e| ((group, e), is_lazy)));
+            }
+            Transition::Group(l, e) => stack.push(((Some(*l), *e), is_lazy)),
+            Transition::Label(_, _) | Transition::Accept | Transition::Eof => {
+                set.push(((group, state), is_lazy));
+            }
+        }
+    }
+
+    set.sort_unstable_by_key(|&((group, s), is_lazy)| (s.0, group.map(<&str>::from), is_lazy));
+    set.dedup();
+    set
+}
+
+/// Drop the lazy states of a [`lazy_closure`] for the groups which reached the accept state,
+/// a lazy quantifier stops repeating as soon as its group has matched.
+fn stop_lazy(nfa: &NFA, set: Vec<(GroupState, bool)>) -> Vec<GroupState> {
+    let matched = set
+        .iter()
+        .filter(|&&((_, s), _)| s == nfa.accept)
+        .map(|&((group, _), _)| group)
+        .collect::<Vec<_>>();
+    let mut set = set
+        .into_iter()
+        .filter(|&((group, _), is_lazy)| !is_lazy || !matched.contains(&group))
+        .map(|(state, _)| state)
+        .collect::<Vec<_>>();
+    set.dedup();
+    set
+}
+
+/// Split the chars accepted by `lits` into disjoint classes.
+/// Two chars in the same class are accepted by exactly the same literals.
+fn char_classes<'a>(lits: impl Iterator<Item = &'a Lit>) -> Vec<RangeInclusive<char>> {
+    let intervals = lits
+        .map(|lit| match lit {
+            &Lit::Char(c) => (c as u32, c as u32),
+            Lit::Any => (0, char::MAX as u32),
+            Lit::Range(r) => (*r.start() as u32, *r.end() as u32),
+        })
+        .collect::<Vec<_>>();
+
+    let bounds = intervals
+        .iter()
+        .flat_map(|&(lo, hi)| [lo, hi + 1])
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut classes = vec![];
+    for w in bounds.windows(2) {
+        let (lo, hi) = (w[0], w[1] - 1);
+        if !intervals.iter().any(|&(l, h)| l <= lo && lo <= h) {
+            continue;
+        }
+
+        // Skip the surrogate code points, they are not valid chars
+        let lo = if (0xD800..=0xDFFF).contains(&lo) {
+            0xE000
+        } else {
+            lo
+        };
+        let hi = if (0xD800..=0xDFFF).contains(&hi) {
+            0xD7FF
+        } else {
+            hi
+        };
+        if let (Some(lo), Some(hi)) = (char::from_u32(lo), char::from_u32(hi)) {
+            if lo <= hi {
+                classes.push(lo..=hi);
+            }
+        }
+    }
+
+    classes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes() {
+        assert_eq!(
+            char_classes(
+                [
+                    Lit::Any,
+                    Lit::Char('a'),
+                    Lit::Range('\u{D000}'..='\u{E000}')
+                ]
+                .iter()
+            ),
+            vec![
+                '\0'..='`',
+                'a'..='a',
+                'b'..='\u{CFFF}',
+                '\u{D000}'..='\u{E000}',
+                '\u{E001}'..=char::MAX
+            ]
+        );
+
+        let alphabet = char_classes([Lit::Char('x'), Lit::Range('0'..='9')].iter());
+        assert_eq!(alphabet, vec!['0'..='9', 'x'..='x']);
+        assert_eq!(class_of(&alphabet, '5'), Some(0));
+        assert_eq!(class_of(&alphabet, 'x'), Some(1));
+        assert_eq!(class_of(&alphabet, 'a'), None);
+    }
+}
@@ -0,0 +1,324 @@
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use crate::{
+    language::{Label, Match},
+    nfa::{NFASet, State, Transition},
+};
+
+use super::{
+    minimize::minimize,
+    powerset::{class_of, GroupState, Powerset},
+    DeterminizeError,
+};
+
+/// A DFA built from a [`NFASet`] which finds the longest match at the start of the input.
+///
+/// Each state stores the label with the highest priority among the NFAs accepting in that state,
+/// so a match is found with a single scan over the input.
+#[derive(Debug)]
+pub struct TokenDFA {
+    /// Disjoint character classes in ascending order.
+    pub alphabet: Vec<RangeInclusive<char>>,
+    /// `transitions[state][class]` is the state reached after reading a char in `alphabet[class]`.
+    pub transitions: Vec<Vec<Option<State>>>,
+    pub start: State,
+    /// The accepted label with the highest priority in each state.
+    pub accept: Vec<Option<Label>>,
+    /// Same as `accept` but for when the whole input has been consumed.
+    /// Also contains the labels of patterns ending with `$`.
+    pub accept_eof: Vec<Option<Label>>,
+}
+
+impl TokenDFA {
+    /// Determinize the combined NFA in `set`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the DFA needs more than `max_states` states.
+    pub fn build(set: &NFASet, max_states: usize) -> Result<Self, DeterminizeError> {
+        let powerset = Powerset::build(&set.nfa, max_states, true)?;
+
+        // The accepted label with the highest priority
+        let best = |states: &[GroupState], accepts: &dyn Fn(State) -> bool| {
+            states
+                .iter()
+                .filter(|&&(_, s)| accepts(s))
+                .filter_map(|&(group, _)| group)
+                .min_by_key(|l| set.priority(*l).unwrap_or(usize::MAX))
+        };
+
+        let accept = powerset
+            .sets
+            .iter()
+            .map(|states| best(states, &|s| s == set.nfa.accept))
+            .collect();
+
+        let accept_eof = powerset
+            .sets
+            .iter()
+            .map(|states| {
+                best(states, &|s| {
+                    matches!(set.nfa[s], Transition::Accept | Transition::Eof)
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            alphabet: powerset.alphabet,
+            transitions: powerset.transitions,
+            start: State(0),
+            accept,
+            accept_eof,
+        })
+    }
+
+    /// Merge equivalent states and remove the states which can't reach an accepting state.
+    #[must_use]
+    pub fn minimize(&self) -> Self {
+        let minimized = minimize(self.start, &self.transitions, |s| {
+            let output = (self.accept[s], self.accept_eof[s]);
+            (output != (None, None)).then_some(output)
+        });
+
+        Self {
+            alphabet: self.alphabet.clone(),
+            transitions: minimized.transitions,
+            start: State(0),
+            accept: minimized
+                .representatives
+                .iter()
+                .map(|&s| self.accept[s])
+                .collect(),
+            accept_eof: minimized
+                .representatives
+                .iter()
+                .map(|&s| self.accept_eof[s])
+                .collect(),
+        }
+    }
+
+    /// Returns the index of the class in `alphabet` which contains `c`.
+    #[must_use]
+    pub fn class_of(&self, c: char) -> Option<usize> {
+        class_of(&self.alphabet, c)
+    }
+
+    /// Find the longest match at the start of `input`,
+    /// with the same result as [`NFASet::longest_match`].
+    ///
+    /// Reads the input until no more transitions are possible,
+    /// remembering the last accepting state along the way.
+    #[must_use]
+    pub fn longest_match(&self, input: &str) -> Option<Match> {
+        match self.partial_match(input, true) {
+            PartialMatch::Complete(m) => m,
+            PartialMatch::Incomplete => unreachable!("The input has ended"),
+        }
+    }
+
+    /// Find the longest match at the start of `input`, where `input` might only be
+    /// the start of the full input. `is_end` is true if nothing follows `input`.
+    ///
+    /// Returns [`PartialMatch::Incomplete`] if more input could change the result.
+    #[must_use]
+    pub fn partial_match(&self, input: &str, is_end: bool) -> PartialMatch {
+        self.resume(&mut self.start_scan(), input, is_end)
+    }
+
+    /// Start a [`Scan`] at the start of the input, see [`TokenDFA::resume`].
+    #[must_use]
+    pub fn start_scan(&self) -> Scan {
+        Scan {
+            state: self.start,
+            read: 0,
+            last: self.accept[self.start].map(|label| Match::Group(label, 0)),
+        }
+    }
+
+    /// Same as [`TokenDFA::partial_match`], but continues from where `scan` stopped.
+    /// `input` must start with the input given in the earlier calls with `scan`,
+    /// only the chars after it are read.
+    #[must_use]
+    pub fn resume(&self, scan: &mut Scan, input: &str, is_end: bool) -> PartialMatch {
+        for (i, c) in input[scan.read..].char_indices() {
+            let end = scan.read + i + c.len_utf8();
+            match self
+                .class_of(c)
+                .and_then(|class| self.transitions[scan.state][class])
+            {
+                Some(next) => scan.state = next,
+                None => return PartialMatch::Complete(scan.last),
+            }
+
+            if let Some(label) = self.accept[scan.state] {
+                scan.last = Some(Match::Group(label, end));
+            }
+        }
+        scan.read = input.len();
+
+        if is_end {
+            PartialMatch::Complete(
+                self.accept_eof[scan.state]
+                    .map(|label| Match::Group(label, input.len()))
+                    .or(scan.last),
+            )
+        } else {
+            PartialMatch::Incomplete
+        }
+    }
+}
+
+/// The progress of [`TokenDFA::resume`] over input which arrives in pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scan {
+    state: State,
+    /// Bytes of the input which have been read.
+    read: usize,
+    /// The longest match so far.
+    last: Option<Match>,
+}
+
+/// Result of [`TokenDFA::partial_match`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialMatch {
+    /// The longest match, no more input is needed.
+    Complete(Option<Match>),
+    /// All input was read without rejecting, more input is needed.
+    Incomplete,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{language::Language, nfa::NFA};
+
+    use super::*;
+
+    fn set(rules: &[(&'static str, &'static str)]) -> NFASet {
+        NFASet::build(
+            rules
+                .iter()
+                .map(|&(label, pattern)| (label.into(), NFA::try_from_language(pattern).unwrap()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn longest_match() {
+        let set = set(&[
+            ("if", "if"),
+            ("ident", "(a-z)(a-z|0-9)*"),
+            ("num", "(0-9)+"),
+            ("arrow", "\\-\\-?>"),
+            ("minus", "\\-"),
+            ("end", "\\.$"),
+        ]);
+        let dfa = set.determinize();
+        let minimized = dfa.minimize();
+
+        for input in [
+            "if", "iff", "if2 x", "i", "123abc", "->", "-->", "--", "-", ".", "..", "", " if",
+            "\u{e5}",
+        ] {
+            assert_eq!(
+                dfa.longest_match(input),
+                set.longest_match(input),
+                "Input: {input:?}"
+            );
+            assert_eq!(
+                minimized.longest_match(input),
+                set.longest_match(input),
+                "Input: {input:?}"
+            );
+        }
+
+        let m = |label: &'static str, size| Some(Match::Group(label.into(), size));
+        assert_eq!(dfa.longest_match("if"), m("if", 2));
+        assert_eq!(dfa.longest_match("if("), m("if", 2));
+        assert_eq!(dfa.longest_match("ifs"), m("ident", 3));
+        assert_eq!(dfa.longest_match("--x"), m("minus", 1));
+        assert_eq!(dfa.longest_match("."), m("end", 1));
+        assert_eq!(dfa.longest_match(".."), None);
+        assert_eq!(dfa.longest_match("!"), None);
+
+        assert_eq!(dfa.partial_match("if", false), PartialMatch::Incomplete);
+        assert_eq!(
+            dfa.partial_match("if ", false),
+            PartialMatch::Complete(m("if", 2))
+        );
+        assert_eq!(dfa.partial_match(".", false), PartialMatch::Incomplete);
+        assert_eq!(
+            dfa.partial_match(".", true),
+            PartialMatch::Complete(m("end", 1))
+        );
+        assert_eq!(dfa.partial_match("", false), PartialMatch::Incomplete);
+
+        let mut scan = dfa.start_scan();
+        assert_eq!(dfa.resume(&mut scan, "i", false), PartialMatch::Incomplete);
+        assert_eq!(
+            dfa.resume(&mut scan, "if2", false),
+            PartialMatch::Incomplete
+        );
+        assert_eq!(
+            dfa.resume(&mut scan, "if2 x", false),
+            PartialMatch::Complete(m("ident", 3))
+        );
+    }
+
+    #[test]
+    fn minimize() {
+        let set = set(&[("x", "ab|cb"), ("y", "(0-9)+|(0-9)+\\.")]);
+        let dfa = set.determinize();
+        assert_eq!(dfa.transitions.len(), 6);
+
+        let minimized = dfa.minimize();
+        assert_eq!(minimized.transitions.len(), 5);
+        for input in ["ab", "cb", "ac", "12", "1.", "1.2", ""] {
+            assert_eq!(minimized.longest_match(input), set.longest_match(input));
+        }
+    }
+
+    #[test]
+    fn lazy() {
+        let set = set(&[("string", r#""(a-z|\ |")*?""#), ("ident", "(a-z)+?x*")]);
+        let dfa = set.determinize();
+        let minimized = dfa.minimize();
+
+        for input in [r#""ab" "c""#, r#""""#, r#""a"#, "abx", "axx", ""] {
+            assert_eq!(
+                dfa.longest_match(input),
+                set.longest_match(input),
+                "Input: {input:?}"
+            );
+            assert_eq!(minimized.longest_match(input), set.longest_match(input));
+        }
+
+        let m = |label: &'static str, size| Some(Match::Group(label.into(), size));
+        assert_eq!(dfa.longest_match(r#""ab" "c""#), m("string", 4));
+        assert_eq!(dfa.longest_match("abx"), m("ident", 1));
+        assert_eq!(dfa.longest_match("axx"), m("ident", 3));
+    }
+
+    #[test]
+    fn lazy_in_loop() {
+        let set = set(&[("loop", "(a*?b)*"), ("plus", "(a*?b)+")]);
+        let dfa = set.determinize();
+        let m = |label: &'static str, size| Some(Match::Group(label.into(), size));
+        for (input, size) in [("abaab", 5), ("aab", 3), ("c", 0)] {
+            assert_eq!(set.longest_match(input), m("loop", size));
+            assert_eq!(dfa.longest_match(input), m("loop", size));
+            assert_eq!(dfa.minimize().longest_match(input), m("loop", size));
+        }
+    }
+
+    #[test]
+    fn max_states() {
+        let set = set(&[("a", "a+"), ("b", "b+")]);
+        assert_eq!(
+            TokenDFA::build(&set, 2).unwrap_err(),
+            DeterminizeError::TooLarge { max_states: 2 }
+        );
+        assert_eq!(TokenDFA::build(&set, 3).unwrap().transitions.len(), 3);
+    }
+}
@@ -0,0 +1,451 @@
+//! A layered drawing of a graph without Graphviz, a basic version of the Sugiyama method:
+//!
+//! 1. Reverse the edges which close a cycle.
+//! 2. Put each node in the layer below all its predecessors.
+//! 3. Split edges spanning several layers with dummy nodes.
+//! 4. Reorder each layer by the mean position of its neighbours to reduce crossings.
+
+use super::{DiGraph, Edge, Shape};
+
+const RADIUS: f64 = 18.0;
+/// Space around the label of a node with `shape=none`.
+const TEXT_RADIUS: f64 = 8.0;
+const LAYER_SPACING: f64 = 90.0;
+const NODE_SPACING: f64 = 80.0;
+const MARGIN: f64 = 30.0;
+/// How far edges between neighbouring layers bend when they point upwards.
+const BEND: f64 = 25.0;
+const SWEEPS: usize = 4;
+
+/// Render the nodes and edges of `graph` as SVG.
+pub(super) fn render(graph: &DiGraph) -> String {
+    Layout::new(graph).svg(graph)
+}
+
+#[derive(Debug)]
+struct Layout {
+    /// The center of each node, followed by the dummy nodes.
+    points: Vec<(f64, f64)>,
+    /// The nodes each edge passes through from its source to its target, empty for self-loops.
+    paths: Vec<Vec<usize>>,
+    /// The edges which point upwards.
+    reversed: Vec<bool>,
+    width: f64,
+    height: f64,
+}
+
+impl Layout {
+    fn new(graph: &DiGraph) -> Self {
+        let reversed = back_edges(graph.nodes.len(), &graph.edges);
+        let mut layer_of = layers(graph.nodes.len(), &graph.edges, &reversed);
+
+        // Add a dummy node in each layer a long edge passes
+        let mut paths = vec![];
+        for (edge, &reversed) in graph.edges.iter().zip(&reversed) {
+            if edge.from == edge.to {
+                paths.push(vec![]);
+                continue;
+            }
+
+            let (top, bottom) = if reversed {
+                (edge.to, edge.from)
+            } else {
+                (edge.from, edge.to)
+            };
+            let mut path = vec![top];
+            for layer in layer_of[top] + 1..layer_of[bottom] {
+                layer_of.push(layer);
+                path.push(layer_of.len() - 1);
+            }
+            path.push(bottom);
+            paths.push(path);
+        }
+
+        let mut layers = vec![vec![]; layer_of.iter().max().map_or(0, |l| l + 1)];
+        for (node, &layer) in layer_of.iter().enumerate() {
+            layers[layer].push(node);
+        }
+        order(&mut layers, &paths);
+
+        let widest = layers.iter().map(Vec::len).max().unwrap_or(0);
+        let mut points = vec![(0.0, 0.0); layer_of.len()];
+        for (layer, nodes) in layers.iter().enumerate() {
+            // Center each layer
+            let offset = (widest - nodes.len()) as f64 / 2.0;
+            for (i, &node) in nodes.iter().enumerate() {
+                points[node] = (
+                    MARGIN + RADIUS + (offset + i as f64) * NODE_SPACING,
+                    MARGIN + RADIUS + layer as f64 * LAYER_SPACING,
+                );
+            }
+        }
+
+        for (path, &reversed) in paths.iter_mut().zip(&reversed) {
+            if reversed {
+                path.reverse();
+            }
+        }
+
+        Self {
+            points,
+            paths,
+            reversed,
+            // Leave room for self-loops on the right
+            width: 2.0 * (MARGIN + 2.0 * RADIUS) + widest.saturating_sub(1) as f64 * NODE_SPACING,
+            height: 2.0 * (MARGIN + RADIUS) + layers.len().saturating_sub(1) as f64 * LAYER_SPACING,
+        }
+    }
+
+    fn svg(&self, graph: &DiGraph) -> String {
+        let (width, height) = (self.width, self.height);
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}">
+<defs>
+<marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="7" markerHeight="7" orient="auto">
+<path d="M 0 0 L 10 5 L 0 10 z"/>
+</marker>
+</defs>
+<g font-family="Times,serif" font-size="14">
+"#
+        );
+
+        for ((edge, path), &reversed) in graph.edges.iter().zip(&self.paths).zip(&self.reversed) {
+            let (d, (x, y)) = if path.is_empty() {
+                self.self_loop(edge.from)
+            } else {
+                self.line(graph, path, reversed)
+            };
+            svg += &format!(
+                "<path d=\"{d}\" fill=\"none\" stroke=\"black\" marker-end=\"url(#arrow)\"/>\n"
+            );
+            if let Some(label) = &edge.label {
+                svg += &format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                    x + 5.0,
+                    y + 5.0,
+                    escape(label)
+                );
+            }
+        }
+
+        for (node, &(x, y)) in graph.nodes.iter().zip(&self.points) {
+            if node.shape != Shape::None {
+                svg += &format!("<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{RADIUS}\" fill=\"white\" stroke=\"black\"/>\n");
+            }
+            if node.shape == Shape::DoubleCircle {
+                svg += &format!(
+                    "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{}\" fill=\"none\" stroke=\"black\"/>\n",
+                    RADIUS - 4.0
+                );
+            }
+            svg += &format!(
+                "<text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n",
+                y + 5.0,
+                escape(node.label.as_ref().unwrap_or(&node.id))
+            );
+        }
+
+        svg + "</g>\n</svg>\n"
+    }
+
+    /// The SVG path of an edge through `path`, and where to put its label.
+    /// Upward edges between neighbouring layers are bent so they don't hide an edge going down.
+    fn line(&self, graph: &DiGraph, path: &[usize], reversed: bool) -> (String, (f64, f64)) {
+        let radius = |node: usize| match graph.nodes.get(node).map(|n| n.shape) {
+            Some(Shape::None) => TEXT_RADIUS,
+            Some(_) => RADIUS,
+            None => 0.0,
+        };
+        let mut points = path.iter().map(|&n| self.points[n]).collect::<Vec<_>>();
+        let last = points.len() - 1;
+        points[0] = towards(points[0], points[1], radius(path[0]));
+        points[last] = towards(points[last], points[last - 1], radius(path[last]));
+
+        let (x0, y0) = points[0];
+        if reversed && points.len() == 2 {
+            let (x1, y1) = points[1];
+            let length = (x1 - x0).hypot(y1 - y0).max(1.0);
+            let control = (
+                (x0 + x1) / 2.0 + (y1 - y0) / length * BEND,
+                (y0 + y1) / 2.0 - (x1 - x0) / length * BEND,
+            );
+            // The middle of the curve
+            let label = (
+                (x0 + x1) / 4.0 + control.0 / 2.0,
+                (y0 + y1) / 4.0 + control.1 / 2.0,
+            );
+            let d = format!(
+                "M {x0:.1} {y0:.1} Q {:.1} {:.1} {x1:.1} {y1:.1}",
+                control.0, control.1
+            );
+            return (d, label);
+        }
+
+        let mut d = format!("M {x0:.1} {y0:.1}");
+        for (x, y) in &points[1..] {
+            d += &format!(" L {x:.1} {y:.1}");
+        }
+
+        let middle = (points.len() - 1) / 2;
+        let (a, b) = (points[middle], points[middle + 1]);
+        (d, ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0))
+    }
+
+    /// A loop on the right side of `node`.
+    fn self_loop(&self, node: usize) -> (String, (f64, f64)) {
+        let (x, y) = self.points[node];
+        let (dx, dy) = (RADIUS * 0.87, RADIUS * 0.5);
+        let d = format!(
+            "M {:.1} {:.1} C {:.1} {:.1} {:.1} {:.1} {:.1} {:.1}",
+            x + dx,
+            y - dy,
+            x + 2.5 * RADIUS,
+            y - 1.5 * RADIUS,
+            x + 2.5 * RADIUS,
+            y + 1.5 * RADIUS,
+            x + dx,
+            y + dy
+        );
+        (d, (x + 2.2 * RADIUS, y))
+    }
+}
+
+/// The point `distance` from `from` in the direction of `to`.
+fn towards(from: (f64, f64), to: (f64, f64), distance: f64) -> (f64, f64) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx.hypot(dy);
+    if length <= distance {
+        return from;
+    }
+    (
+        from.0 + dx / length * distance,
+        from.1 + dy / length * distance,
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Find the edges closing a cycle with a depth first search,
+/// starting from the nodes without incoming edges.
+fn back_edges(nodes: usize, edges: &[Edge]) -> Vec<bool> {
+    let mut out = vec![vec![]; nodes];
+    let mut sources = vec![true; nodes];
+    for (i, edge) in edges.iter().enumerate() {
+        out[edge.from].push(i);
+        sources[edge.to] &= edge.from == edge.to;
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Mark {
+        New,
+        Active,
+        Done,
+    }
+
+    let mut reversed = vec![false; edges.len()];
+    let mut mark = vec![Mark::New; nodes];
+    let roots = (0..nodes).filter(|&n| sources[n]).chain(0..nodes);
+
+    for root in roots {
+        if mark[root] != Mark::New {
+            continue;
+        }
+        mark[root] = Mark::Active;
+        let mut stack = vec![(root, 0)];
+
+        while let Some(&(node, next)) = stack.last() {
+            let Some(&edge) = out[node].get(next) else {
+                mark[node] = Mark::Done;
+                stack.pop();
+                continue;
+            };
+            let top = stack.len() - 1;
+            stack[top].1 += 1;
+
+            let to = edges[edge].to;
+            match mark[to] {
+                Mark::New => {
+                    mark[to] = Mark::Active;
+                    stack.push((to, 0));
+                }
+                Mark::Active => reversed[edge] = to != node,
+                Mark::Done => {}
+            }
+        }
+    }
+
+    reversed
+}
+
+/// The layer of each node, the length of the longest path reaching it.
+fn layers(nodes: usize, edges: &[Edge], reversed: &[bool]) -> Vec<usize> {
+    let mut out = vec![vec![]; nodes];
+    let mut incoming = vec![0; nodes];
+    for (edge, &reversed) in edges.iter().zip(reversed) {
+        if edge.from == edge.to {
+            continue;
+        }
+        let (from, to) = if reversed {
+            (edge.to, edge.from)
+        } else {
+            (edge.from, edge.to)
+        };
+        out[from].push(to);
+        incoming[to] += 1;
+    }
+
+    let mut layer = vec![0; nodes];
+    let mut stack = (0..nodes).filter(|&n| incoming[n] == 0).collect::<Vec<_>>();
+    while let Some(node) = stack.pop() {
+        for &next in &out[node] {
+            layer[next] = layer[next].max(layer[node] + 1);
+            incoming[next] -= 1;
+            if incoming[next] == 0 {
+                stack.push(next);
+            }
+        }
+    }
+
+    layer
+}
+
+/// Sweep down and up the layers, sorting each layer by the mean position of its neighbours
+/// in the previous layer. Keeps the order with the fewest crossings.
+fn order(layers: &mut [Vec<usize>], paths: &[Vec<usize>]) {
+    let nodes = layers.iter().map(Vec::len).sum();
+    let mut above = vec![vec![]; nodes];
+    let mut below = vec![vec![]; nodes];
+    for path in paths {
+        for pair in path.windows(2) {
+            below[pair[0]].push(pair[1]);
+            above[pair[1]].push(pair[0]);
+        }
+    }
+
+    let mut position = vec![0; nodes];
+    for layer in layers.iter() {
+        for (i, &node) in layer.iter().enumerate() {
+            position[node] = i;
+        }
+    }
+
+    let mut best = layers.to_vec();
+    let mut fewest = crossings(layers, &below, &position);
+
+    for _ in 0..SWEEPS {
+        for layer in layers.iter_mut().skip(1) {
+            sort_layer(layer, &above, &mut position);
+        }
+        for layer in layers.iter_mut().rev().skip(1) {
+            sort_layer(layer, &below, &mut position);
+        }
+
+        let count = crossings(layers, &below, &position);
+        if count < fewest {
+            best = layers.to_vec();
+            fewest = count;
+        }
+    }
+
+    layers.clone_from_slice(&best);
+}
+
+fn sort_layer(layer: &mut [usize], neighbours: &[Vec<usize>], position: &mut [usize]) {
+    let key = |(i, node): (usize, &usize)| {
+        let neighbours = &neighbours[*node];
+        if neighbours.is_empty() {
+            i as f64
+        } else {
+            neighbours.iter().map(|&n| position[n] as f64).sum::<f64>() / neighbours.len() as f64
+        }
+    };
+    let mut keyed = layer
+        .iter()
+        .enumerate()
+        .map(|entry| (key(entry), *entry.1))
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (i, (slot, (_, node))) in layer.iter_mut().zip(keyed).enumerate() {
+        *slot = node;
+        position[node] = i;
+    }
+}
+
+/// Number of pairs of edges crossing between neighbouring layers.
+fn crossings(layers: &[Vec<usize>], below: &[Vec<usize>], position: &[usize]) -> usize {
+    let mut count = 0;
+    for layer in layers {
+        let edges = layer
+            .iter()
+            .flat_map(|&node| below[node].iter().map(move |&next| (node, next)))
+            .map(|(a, b)| (position[a], position[b]))
+            .collect::<Vec<_>>();
+
+        for (i, a) in edges.iter().enumerate() {
+            for b in &edges[i + 1..] {
+                if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Node;
+    use super::*;
+
+    fn graph(nodes: usize, edges: &[(usize, usize)]) -> DiGraph {
+        DiGraph {
+            nodes: (0..nodes)
+                .map(|n| Node {
+                    id: n.to_string(),
+                    label: None,
+                    shape: Shape::Circle,
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|&(from, to)| Edge {
+                    from,
+                    to,
+                    label: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn layering() {
+        // 3 has no incoming edges, so the search starts there
+        let graph = graph(4, &[(0, 1), (1, 2), (2, 0), (0, 2), (3, 0), (1, 1)]);
+        let reversed = back_edges(4, &graph.edges);
+        assert_eq!(reversed, vec![false, false, true, false, false, false]);
+        assert_eq!(layers(4, &graph.edges, &reversed), vec![1, 2, 3, 0]);
+
+        // The edges 0 -> 2 and 2 -> 0 skip a layer
+        let layout = Layout::new(&graph);
+        assert_eq!(layout.points.len(), 6);
+        assert_eq!(layout.paths[2], vec![2, 4, 0]);
+        assert_eq!(layout.paths[3], vec![0, 5, 2]);
+        assert!(layout.paths[5].is_empty());
+    }
+
+    #[test]
+    fn crossing() {
+        // 0 -> 3 and 1 -> 2 cross unless 2 and 3 swap places
+        let graph = graph(4, &[(0, 3), (1, 2)]);
+        let layout = Layout::new(&graph);
+        assert_eq!(layout.points[0].1, layout.points[1].1);
+        assert!(layout.points[0].0 < layout.points[1].0);
+        assert!(layout.points[3].0 < layout.points[2].0);
+    }
+}
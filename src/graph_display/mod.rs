@@ -0,0 +1,388 @@
+#[cfg(feature = "exec-dot")]
+use graphviz_rust::cmd::{Format, Layout};
+#[cfg(feature = "exec-dot")]
+use graphviz_rust::exec_dot;
+
+use std::collections::HashMap;
+
+use crate::dfa::DFA;
+use crate::nfa::EpsilonFreeNFA;
+use crate::nfa::State;
+use crate::nfa::Transition;
+use crate::nfa::NFA;
+use crate::transducer::{Mealy, Moore};
+
+mod layout;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Circle,
+    DoubleCircle,
+    /// Only the label is drawn.
+    None,
+}
+
+#[derive(Debug)]
+struct Node {
+    id: String,
+    /// Drawn instead of `id`.
+    label: Option<String>,
+    shape: Shape,
+}
+
+#[derive(Debug)]
+struct Edge {
+    /// Index of the source in [`DiGraph::nodes`].
+    from: usize,
+    to: usize,
+    label: Option<String>,
+}
+
+/// A directed graph of an automaton, drawn as SVG or printed in the DOT language.
+#[derive(Debug)]
+pub struct DiGraph {
+    nodes: Vec<Node>,
+    /// There is at most one edge for each source and target.
+    edges: Vec<Edge>,
+}
+
+impl From<&NFA> for DiGraph {
+    fn from(nfa: &NFA) -> Self {
+        let edges = nfa
+            .transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(state, transition)| {
+                let label = match transition {
+                    Transition::Label(l, _) => format!("'{l}'"),
+                    Transition::Group(g, _) => format!("G: {g}"),
+                    _ => String::new(),
+                };
+                transition
+                    .next()
+                    .into_iter()
+                    .flatten()
+                    .map(move |e| (State(state), label.clone(), e))
+            });
+
+        Self::automaton(
+            nfa.transitions.len(),
+            nfa.start,
+            |s| s == nfa.accept || s == nfa.eof,
+            |_| None,
+            edges,
+        )
+    }
+}
+
+impl From<&DFA> for DiGraph {
+    fn from(dfa: &DFA) -> Self {
+        let edges = dfa
+            .transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(state, transitions)| {
+                dfa.alphabet
+                    .iter()
+                    .zip(transitions)
+                    .filter_map(move |(class, e)| {
+                        let label = if class.start() == class.end() {
+                            class.start().escape_default().to_string()
+                        } else {
+                            format!(
+                                "{}-{}",
+                                class.start().escape_default(),
+                                class.end().escape_default()
+                            )
+                        };
+                        e.map(|e| (State(state), label, e))
+                    })
+            });
+
+        Self::automaton(
+            dfa.transitions.len(),
+            dfa.start,
+            |s| dfa.accept.contains(&s),
+            |_| None,
+            edges,
+        )
+    }
+}
+
+impl From<&EpsilonFreeNFA> for DiGraph {
+    fn from(nfa: &EpsilonFreeNFA) -> Self {
+        let edges = nfa
+            .transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(state, transitions)| {
+                transitions
+                    .iter()
+                    .map(move |(lit, e)| (State(state), lit.to_string(), *e))
+            });
+
+        Self::automaton(
+            nfa.transitions.len(),
+            nfa.start,
+            |s| !nfa.accept[s.0].is_empty() || !nfa.accept_eof[s.0].is_empty(),
+            |_| None,
+            edges,
+        )
+    }
+}
+
+impl<I: std::fmt::Display, O: std::fmt::Display> From<&Mealy<I, O>> for DiGraph {
+    /// Edges are labeled `input/output`.
+    fn from(mealy: &Mealy<I, O>) -> Self {
+        let edges = mealy
+            .transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(state, transitions)| {
+                mealy
+                    .alphabet
+                    .iter()
+                    .zip(transitions)
+                    .filter_map(move |(symbol, next)| {
+                        let (e, output) = next.as_ref()?;
+                        Some((State(state), format!("{symbol}/{output}"), *e))
+                    })
+            });
+
+        Self::automaton(
+            mealy.transitions.len(),
+            mealy.start,
+            |_| false,
+            |_| None,
+            edges,
+        )
+    }
+}
+
+impl<I: std::fmt::Display, O: std::fmt::Display> From<&Moore<I, O>> for DiGraph {
+    /// Nodes are labeled `state/output` and edges with the input.
+    fn from(moore: &Moore<I, O>) -> Self {
+        let edges = moore
+            .transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(state, transitions)| {
+                moore
+                    .alphabet
+                    .iter()
+                    .zip(transitions)
+                    .filter_map(move |(symbol, e)| e.map(|e| (State(state), symbol.to_string(), e)))
+            });
+
+        Self::automaton(
+            moore.transitions.len(),
+            moore.start,
+            |_| false,
+            |s| {
+                let output = moore.outputs.get(s.0)?;
+                Some(format!("{s}/{output}"))
+            },
+            edges,
+        )
+    }
+}
+
+/// Quote `label` so it can be used as a DOT attribute.
+fn quote(label: &str) -> String {
+    format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl DiGraph {
+    /// Graph of an automaton with the states `0..states` and an arrow from "start" to `start`.
+    /// Accepting states are drawn with a double circle, `node_label` replaces the state number.
+    ///
+    /// `edges` are `(from, label, to)`, all edges with the same source and target are
+    /// merged into a single edge. Edges with an empty label are drawn without a label.
+    fn automaton(
+        states: usize,
+        start: State,
+        accepting: impl Fn(State) -> bool,
+        node_label: impl Fn(State) -> Option<String>,
+        edges: impl IntoIterator<Item = (State, String, State)>,
+    ) -> Self {
+        let mut nodes = (0..states)
+            .map(State)
+            .map(|state| Node {
+                id: state.to_string(),
+                label: node_label(state),
+                shape: if accepting(state) {
+                    Shape::DoubleCircle
+                } else {
+                    Shape::Circle
+                },
+            })
+            .collect::<Vec<_>>();
+        nodes.push(Node {
+            id: "start".into(),
+            label: None,
+            shape: Shape::None,
+        });
+
+        let mut merged = vec![Edge {
+            from: states,
+            to: start.0,
+            label: None,
+        }];
+        let mut labels: Vec<Vec<String>> = vec![vec![]];
+        let mut index = HashMap::new();
+        for (from, label, to) in edges {
+            let i = *index.entry((from, to)).or_insert_with(|| {
+                merged.push(Edge {
+                    from: from.0,
+                    to: to.0,
+                    label: None,
+                });
+                labels.push(vec![]);
+                merged.len() - 1
+            });
+            if !label.is_empty() {
+                labels[i].push(label);
+            }
+        }
+
+        for (edge, labels) in merged.iter_mut().zip(labels) {
+            if !labels.is_empty() {
+                edge.label = Some(labels.join(", "));
+            }
+        }
+
+        Self {
+            nodes,
+            edges: merged,
+        }
+    }
+
+    /// The graph in the DOT language.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("strict digraph G {\n");
+
+        for node in &self.nodes {
+            let mut attributes = vec![];
+            match node.shape {
+                Shape::Circle => {}
+                Shape::DoubleCircle => attributes.push("shape=doublecircle".to_string()),
+                Shape::None => attributes.push("shape=none".to_string()),
+            }
+            if let Some(label) = &node.label {
+                attributes.push(format!("label={}", quote(label)));
+            }
+
+            if attributes.is_empty() {
+                dot += &format!("    {}\n", node.id);
+            } else {
+                dot += &format!("    {}[{}]\n", node.id, attributes.join(","));
+            }
+        }
+
+        for edge in &self.edges {
+            let (from, to) = (&self.nodes[edge.from].id, &self.nodes[edge.to].id);
+            match &edge.label {
+                Some(label) => dot += &format!("    {from} -> {to} [label={}]\n", quote(label)),
+                None => dot += &format!("    {from} -> {to}\n"),
+            }
+        }
+
+        dot + "}\n"
+    }
+
+    /// Draw the graph as SVG with the built-in layered layout, without Graphviz.
+    #[must_use]
+    pub fn to_svg(&self) -> String {
+        layout::render(self)
+    }
+
+    /// Draw the graph as SVG by running `dot` from Graphviz.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `dot` is not installed or could not draw the graph.
+    #[cfg(feature = "exec-dot")]
+    pub fn to_svg_with_dot(&self) -> std::io::Result<String> {
+        exec_dot(self.to_dot(), vec![Format::Svg.into(), Layout::Dot.into()])
+    }
+
+    /// Draw the graph as SVG with Graphviz if the `exec-dot` feature is enabled and `dot` is installed,
+    /// otherwise with [`DiGraph::to_svg`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if `dot` is installed but could not draw the graph.
+    pub fn render(&self) -> std::io::Result<String> {
+        #[cfg(feature = "exec-dot")]
+        match self.to_svg_with_dot() {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            svg => return svg,
+        }
+
+        Ok(self.to_svg())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg() {
+        use crate::language::Language;
+
+        let nfa = NFA::try_from_language("(a|b)*c").unwrap();
+        let svg = DiGraph::from(&nfa).to_svg();
+        assert!(svg.starts_with("<svg"), "{svg}");
+        assert!(svg.ends_with("</svg>\n"), "{svg}");
+        assert_eq!(
+            svg.matches("<circle").count(),
+            nfa.transitions.len() + 2,
+            "{svg}"
+        );
+        assert!(svg.contains(">'c'</text>"), "{svg}");
+        assert!(svg.contains(">start</text>"), "{svg}");
+    }
+
+    #[test]
+    fn epsilon_free() {
+        use crate::language::Language;
+
+        let nfa = NFA::try_from_language("ab|ac").unwrap();
+        let dot = DiGraph::from(&nfa.eliminate_epsilon()).to_dot();
+        assert!(dot.contains(r#"0 -> 1 [label="a"]"#), "{dot}");
+        assert!(dot.contains("doublecircle"), "{dot}");
+        // Only the edges from start and the labels remain
+        assert_eq!(dot.matches("->").count(), 5, "{dot}");
+    }
+
+    #[test]
+    fn transducer() {
+        let mealy = Mealy {
+            alphabet: vec!['a', 'b', '"'],
+            transitions: vec![vec![
+                Some((State(0), 1)),
+                Some((State(0), 2)),
+                Some((State(0), 3)),
+            ]],
+            start: State(0),
+        };
+        let dot = DiGraph::from(&mealy).to_dot();
+        assert!(dot.contains(r#"label="a/1, b/2, \"/3""#), "{dot}");
+
+        let moore = Moore {
+            alphabet: vec![0, 1],
+            transitions: vec![
+                vec![None, Some(State(1))],
+                vec![Some(State(0)), Some(State(1))],
+            ],
+            outputs: vec!["x", "y"],
+            start: State(0),
+        };
+        let dot = DiGraph::from(&moore).to_dot();
+        assert!(dot.contains(r#"1[label="1/y"]"#), "{dot}");
+        assert!(dot.contains(r#"1 -> 0 [label="0"]"#), "{dot}");
+        assert!(dot.contains(r#"1 -> 1 [label="1"]"#), "{dot}");
+    }
+}
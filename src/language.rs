@@ -1,4 +1,9 @@
-use crate::parse::{ParseError, Token};
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    dfa::DeterminizeError,
+    parse::{ParseError, Token},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CompileError {
@@ -16,8 +21,8 @@ pub enum CompileError {
     ParseError(String),
 }
 
-impl std::fmt::Display for CompileError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::EmptyStack { token } => {
                 writeln!(f, "Empty stack when handling token '{token}'")
@@ -33,24 +38,26 @@ impl std::fmt::Display for CompileError {
     }
 }
 
-impl std::error::Error for CompileError {}
+impl core::error::Error for CompileError {}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LanguageError {
     CompileError(CompileError),
     ParseError(ParseError),
+    DeterminizeError(DeterminizeError),
 }
 
-impl std::fmt::Display for LanguageError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for LanguageError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::CompileError(e) => e.fmt(f),
             Self::ParseError(e) => e.fmt(f),
+            Self::DeterminizeError(e) => e.fmt(f),
         }
     }
 }
 
-impl std::error::Error for LanguageError {}
+impl core::error::Error for LanguageError {}
 
 impl From<CompileError> for LanguageError {
     fn from(e: CompileError) -> Self {
@@ -64,6 +71,12 @@ impl From<ParseError> for LanguageError {
     }
 }
 
+impl From<DeterminizeError> for LanguageError {
+    fn from(e: DeterminizeError) -> Self {
+        Self::DeterminizeError(e)
+    }
+}
+
 pub trait Language: Sized {
     /// Check if `input` is accepted by the regex.
     /// Returns the length of the match from the start, or `None` if no match was found.
@@ -95,8 +108,8 @@ impl From<&'static str> for Label {
     }
 }
 
-impl std::fmt::Display for Label {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Label {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         self.0.fmt(f)
     }
 }
@@ -0,0 +1,73 @@
+/// A 1-based line and column in the input.
+/// The column counts chars, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for LineCol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Converts byte offsets in the input into lines and columns.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'input> {
+    input: &'input str,
+    /// Byte offset of the first char on each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'input> LineIndex<'input> {
+    #[must_use]
+    pub fn new(input: &'input str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { input, line_starts }
+    }
+
+    /// Find the line and column of the byte `offset`.
+    /// Offsets past the end of the input are placed at the end.
+    ///
+    /// # Panics
+    ///
+    /// If `offset` is not on a char boundary.
+    #[must_use]
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.input.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let column = self.input[self.line_starts[line - 1]..offset]
+            .chars()
+            .count();
+
+        LineCol {
+            line,
+            column: column + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col() {
+        let index = LineIndex::new("ab\n\ncd\u{e5}e\n");
+        let lc = |line, column| LineCol { line, column };
+
+        assert_eq!(index.line_col(0), lc(1, 1));
+        assert_eq!(index.line_col(2), lc(1, 3));
+        assert_eq!(index.line_col(3), lc(2, 1));
+        assert_eq!(index.line_col(4), lc(3, 1));
+        assert_eq!(index.line_col(8), lc(3, 4));
+        assert_eq!(index.line_col(10), lc(4, 1));
+        assert_eq!(index.line_col(100), lc(4, 1));
+
+        assert_eq!(index.line_col(8).to_string(), "line 3, column 4");
+    }
+}
@@ -1,13 +1,25 @@
+use std::cell::OnceCell;
 use std::marker::PhantomData;
 
 use self::token::{Spanned, Token};
 
+mod line_index;
+mod recovery;
+mod stream;
 pub mod token;
 
+pub use line_index::{LineCol, LineIndex};
+pub use recovery::{LexDiagnostic, Recovery};
+pub use stream::{CharSource, ReadSource, Source, StreamError, StreamLexer};
+
 pub mod prelude {
-    pub use super::token::{Spanned, Token};
-    pub use super::{LexError, Lexer};
+    pub use super::token::{OwnedSpanned, Spanned, Token};
+    pub use super::{
+        LexDiagnostic, LexError, Lexer, LineCol, LineIndex, Recovery, StreamError, StreamLexer,
+    };
+    pub use automata_rust_derive::Token;
 
+    pub use crate::dfa::TokenDFA;
     pub use crate::impl_token;
     pub use crate::language::Language;
     pub use crate::nfa::{NFASet, NFA};
@@ -15,6 +27,9 @@ pub mod prelude {
 
 #[derive(Debug)]
 pub struct Lexer<'input, T> {
+    /// The complete input.
+    source: &'input str,
+    /// The remaining input.
     input: &'input str,
     consumed: usize,
     phantom: PhantomData<T>,
@@ -23,19 +38,44 @@ pub struct Lexer<'input, T> {
     /// True when an error has been found and we could not skip forward in the input stream.
     /// When this is `true` the iterator only produces `None`.
     sent_error: bool,
+    recovery: Recovery,
+    /// Built on first use.
+    line_index: OnceCell<LineIndex<'input>>,
 }
 
 impl<'input, T> Lexer<'input, T> {
     #[must_use]
     pub fn new(input: &'input str) -> Self {
         Self {
+            source: input,
             input,
             consumed: 0,
             phantom: PhantomData,
             sent_eof: false,
             sent_error: false,
+            recovery: Recovery::default(),
+            line_index: OnceCell::new(),
         }
     }
+
+    /// Set what to do after input which doesn't match any token,
+    /// the default is to skip one char.
+    #[must_use]
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    #[must_use]
+    pub fn line_index(&self) -> &LineIndex<'input> {
+        self.line_index.get_or_init(|| LineIndex::new(self.source))
+    }
+
+    /// Find the line and column of a byte offset, e.g. [`Spanned::start`].
+    #[must_use]
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        self.line_index().line_col(offset)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -59,13 +99,23 @@ impl From<LexError> for String {
 
 impl std::error::Error for LexError {}
 
-impl<'input, T: Token> Iterator for Lexer<'input, T>
-where
-    T: std::fmt::Debug,
-{
-    type Item = Result<Spanned<T>, LexError>;
+impl<'input, T: Token> Lexer<'input, T> {
+    /// Lex the complete input, collecting the skipped input instead of stopping at the first error.
+    #[must_use]
+    pub fn lex_all(mut self) -> (Vec<Spanned<'input, T>>, Vec<LexDiagnostic<'input>>) {
+        let mut tokens = vec![];
+        let mut diagnostics = vec![];
+        while let Some(res) = self.next_token() {
+            match res {
+                Ok(token) => tokens.push(token),
+                Err(diagnostic) => diagnostics.push(diagnostic),
+            }
+        }
+        (tokens, diagnostics)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Same as [`Iterator::next`] but returns the span and text of the skipped input.
+    fn next_token(&mut self) -> Option<Result<Spanned<'input, T>, LexDiagnostic<'input>>> {
         let skipped = T::skip_chars(self.input);
         self.input = &self.input[skipped..];
         self.consumed += skipped;
@@ -80,6 +130,7 @@ where
                 Ok(Spanned {
                     start: self.consumed,
                     token: t,
+                    text: &self.source[self.source.len()..],
                     end: self.consumed,
                 })
             });
@@ -88,31 +139,50 @@ where
         let token = T::next_match(self.input)
             .map(|(consumed, token)| {
                 let start = self.consumed;
+                let text = &self.input[..consumed];
                 self.consumed += consumed;
                 self.input = &self.input[consumed..];
                 Spanned {
                     start,
                     token,
+                    text,
                     end: self.consumed,
                 }
             })
             .ok_or_else(|| {
-                let consumed = self.consumed;
-                // We try to skip one char and continue.
-                if let Some(c) = self.input.chars().next() {
-                    self.input = &self.input[c.len_utf8()..];
-                    self.consumed += c.len_utf8();
-                } else {
-                    // We end the iterator if we can't skip
+                let start = self.consumed;
+                let skip = self
+                    .recovery
+                    .skip_len(self.input, true)
+                    .unwrap_or(self.input.len());
+                let text = &self.input[..skip];
+                self.input = &self.input[skip..];
+                self.consumed += skip;
+
+                if self.recovery == Recovery::Abort {
                     self.sent_error = true;
                 }
-                LexError::UnrecognizedToken(consumed)
+                LexDiagnostic {
+                    span: start..self.consumed,
+                    text,
+                }
             });
 
         Some(token)
     }
 }
 
+impl<'input, T: Token> Iterator for Lexer<'input, T>
+where
+    T: std::fmt::Debug,
+{
+    type Item = Result<Spanned<'input, T>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().map(|res| res.map_err(LexError::from))
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -133,6 +203,65 @@ pub mod tests {
         (Num, "num", r"(0-9)+")
     );
 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CommentToken {
+        Var,
+        Num,
+    }
+
+    impl_token!(
+        CommentToken,
+        None,
+        skip = "(\\n|\\r|\\t|\\ |//(\\ -\u{10FFFF}|\\t|\\r)*(\\n|$))*",
+        (Var, "var", r"(a-z)+"),
+        (Num, "num", r"(0-9)+")
+    );
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ValueToken {
+        Var,
+        Op(char),
+        Num(i64),
+    }
+
+    impl_token!(
+        ValueToken,
+        None,
+        (Var, "var", r"(a-z)+"),
+        (Op(char), "op", r"\+|\-", |s| Op(s.chars().next().unwrap())),
+        (Num(i64), "num", r"(0-9)+", |s| Num(s.parse().unwrap()))
+    );
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum KeywordToken {
+        If,
+        Else,
+        Ident,
+    }
+
+    impl_token!(
+        KeywordToken,
+        None,
+        (If, "if", r"if"),
+        (Else, "else", r"else"),
+        (Ident, "ident", r"(a-z)+")
+    );
+
+    #[derive(Debug, Clone, PartialEq, Eq, Token)]
+    #[token(skip = r"(\n|\r|\t|\ )*")]
+    pub enum DeriveToken {
+        #[token("let")]
+        Let,
+        #[token(r"(a-z)(a-z|0-9)*", convert = |s| Ident(s.to_string()))]
+        Ident(String),
+        #[token(r"(0-9)+", convert = |s| Num(s.parse().unwrap()))]
+        Num(u32),
+        #[token(r"=")]
+        Eq,
+        #[token(eof)]
+        Eof,
+    }
+
     #[test]
     fn lexer() {
         // crate::graph_display::print_nfa_svg(&REG_SET.0);
@@ -150,6 +279,10 @@ pub mod tests {
         use ExprToken::*;
         assert_eq!(tokens, vec![Var, Op, Var, Op, Num, Op, Num, Var],);
 
+        let lexer = Lexer::<ExprToken>::new(input);
+        let texts = lexer.map(|res| res.unwrap().text).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["one1", "+", "two2", "-", "1", "+", "21", "a20"]);
+
         // Invalid '/'
         let input = "zx + yx - xx * (y / x)";
         let lexer = Lexer::<ExprToken>::new(input);
@@ -161,10 +294,7 @@ pub mod tests {
         let lexer = Lexer::<ExprToken>::new(input);
         let tokens = lexer
             .into_iter()
-            .filter_map(|res| match res {
-                Ok(_) => None,
-                Err(err) => Some(err),
-            })
+            .filter_map(Result::err)
             .collect::<Vec<_>>();
 
         assert_eq!(
@@ -175,4 +305,145 @@ pub mod tests {
             ]
         );
     }
+
+    #[test]
+    fn large_input() {
+        // Each token and each skip only reads its own part of the input
+        let input = "ab 12 ".repeat(20_000);
+        let tokens = Lexer::<ExprToken>::new(&input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(tokens.len(), 40_000);
+        assert_eq!(tokens[39_999].start, input.len() - 3);
+    }
+
+    #[test]
+    fn skip() {
+        let input = "// comment \u{e5}\r\nabc 12\t// 34\n\r\n  // x y\n5 // end";
+
+        let lexer = Lexer::<CommentToken>::new(input);
+        let tokens = lexer
+            .map(|res| res.map(|Spanned { token, text, .. }| (token, text)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        use CommentToken::*;
+        assert_eq!(tokens, vec![(Var, "abc"), (Num, "12"), (Num, "5")]);
+
+        // The default pattern does not skip '\r'
+        let lexer = Lexer::<ExprToken>::new("1\r\n2");
+        assert_eq!(
+            lexer.collect::<Vec<_>>()[1],
+            Err(LexError::UnrecognizedToken(1))
+        );
+    }
+
+    #[test]
+    fn values() {
+        let lexer = Lexer::<ValueToken>::new("12 + x - 300");
+        let tokens = lexer.map(|res| res.unwrap().token).collect::<Vec<_>>();
+
+        use ValueToken::*;
+        assert_eq!(tokens, vec![Num(12), Op('+'), Var, Op('-'), Num(300)]);
+    }
+
+    #[test]
+    fn keywords() {
+        let lexer = Lexer::<KeywordToken>::new("if iff else elsewhere i");
+        let tokens = lexer.map(|res| res.unwrap().token).collect::<Vec<_>>();
+
+        use KeywordToken::*;
+        assert_eq!(tokens, vec![If, Ident, Else, Ident, Ident]);
+    }
+
+    #[test]
+    fn derive() {
+        let input = "let x1 =\r\n 42 letter ";
+        let lexer = Lexer::<DeriveToken>::new(input);
+        let spanned = lexer.map(Result::unwrap).collect::<Vec<_>>();
+        let eof = spanned.last().unwrap();
+        assert_eq!(
+            (eof.start, eof.text, eof.end),
+            (input.len(), "", input.len())
+        );
+
+        let tokens = spanned.into_iter().map(|t| t.token).collect::<Vec<_>>();
+
+        use DeriveToken::*;
+        assert_eq!(
+            tokens,
+            vec![
+                Let,
+                Ident("x1".to_string()),
+                Eq,
+                Num(42),
+                Ident("letter".to_string()),
+                Eof
+            ]
+        );
+        assert_eq!(DeriveToken::get_skip_reg(), r"(\n|\r|\t|\ )*");
+    }
+
+    #[test]
+    fn recovery() {
+        let input = "ab + ?? 12 #x 3";
+        let lex = |recovery| {
+            let (tokens, diagnostics) = Lexer::<ExprToken>::new(input)
+                .with_recovery(recovery)
+                .lex_all();
+            (
+                tokens.into_iter().map(|t| t.text).collect::<Vec<_>>(),
+                diagnostics
+                    .into_iter()
+                    .map(|d| (d.span, d.text))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            lex(Recovery::default()),
+            (
+                vec!["ab", "+", "12", "x", "3"],
+                vec![(5..6, "?"), (6..7, "?"), (11..12, "#")]
+            )
+        );
+        assert_eq!(
+            lex(Recovery::SkipToWhitespace),
+            (
+                vec!["ab", "+", "12", "3"],
+                vec![(5..7, "??"), (11..13, "#x")]
+            )
+        );
+        assert_eq!(
+            lex(Recovery::SkipChars(3)),
+            (
+                vec!["ab", "+", "12", "3"],
+                vec![(5..8, "?? "), (11..14, "#x ")]
+            )
+        );
+        assert_eq!(lex(Recovery::Abort), (vec!["ab", "+"], vec![(5..6, "?")]));
+
+        let mut lexer = Lexer::<ExprToken>::new(input).with_recovery(Recovery::Abort);
+        assert_eq!(lexer.nth(2), Some(Err(LexError::UnrecognizedToken(5))));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn line_col() {
+        let input = "one +\n  two\n\n- 3";
+
+        let mut lexer = Lexer::<ExprToken>::new(input);
+        let spans = lexer
+            .by_ref()
+            .map(|res| res.unwrap().start)
+            .collect::<Vec<_>>();
+
+        let locations = spans
+            .into_iter()
+            .map(|offset| lexer.line_col(offset))
+            .map(|LineCol { line, column }| (line, column))
+            .collect::<Vec<_>>();
+
+        assert_eq!(locations, vec![(1, 1), (1, 5), (2, 3), (4, 1), (4, 3)]);
+    }
 }
@@ -0,0 +1,88 @@
+use std::ops::Range;
+
+use super::LexError;
+
+/// What the lexer does after input which doesn't match any token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Skip `n` chars and continue, at least one char is skipped.
+    SkipChars(usize),
+    /// Skip until the next whitespace char and continue.
+    SkipToWhitespace,
+    /// Skip one char and end the lexer.
+    Abort,
+}
+
+impl Default for Recovery {
+    fn default() -> Self {
+        Self::SkipChars(1)
+    }
+}
+
+impl Recovery {
+    /// Number of bytes to skip at the start of `input`, which starts with unrecognized input.
+    /// `is_end` is true if nothing follows `input`.
+    ///
+    /// Returns `None` if more input is needed to decide.
+    #[must_use]
+    pub fn skip_len(self, input: &str, is_end: bool) -> Option<usize> {
+        let mut chars = input.char_indices();
+        let end = match self {
+            Self::SkipChars(n) => chars.nth(n.max(1)),
+            Self::SkipToWhitespace => chars.skip(1).find(|(_, c)| c.is_whitespace()),
+            Self::Abort => chars.nth(1),
+        };
+
+        match end {
+            Some((i, _)) => Some(i),
+            None if is_end => Some(input.len()),
+            None => None,
+        }
+    }
+}
+
+/// Input which was skipped since it didn't match any token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexDiagnostic<'input> {
+    pub span: Range<usize>,
+    /// The skipped input, `&input[span]`.
+    pub text: &'input str,
+}
+
+impl std::fmt::Display for LexDiagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unrecognized token {:?} at {}",
+            self.text, self.span.start
+        )
+    }
+}
+
+impl std::error::Error for LexDiagnostic<'_> {}
+
+impl From<LexDiagnostic<'_>> for LexError {
+    fn from(value: LexDiagnostic<'_>) -> Self {
+        Self::UnrecognizedToken(value.span.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_len() {
+        let input = "#\u{e5}! x";
+        assert_eq!(Recovery::default().skip_len(input, false), Some(1));
+        assert_eq!(Recovery::SkipChars(0).skip_len(input, false), Some(1));
+        assert_eq!(Recovery::SkipChars(2).skip_len(input, false), Some(3));
+        assert_eq!(Recovery::SkipChars(9).skip_len(input, false), None);
+        assert_eq!(Recovery::SkipChars(9).skip_len(input, true), Some(6));
+        assert_eq!(Recovery::SkipToWhitespace.skip_len(input, false), Some(4));
+        assert_eq!(Recovery::SkipToWhitespace.skip_len("#!", false), None);
+        assert_eq!(Recovery::SkipToWhitespace.skip_len("#!", true), Some(2));
+        assert_eq!(Recovery::SkipToWhitespace.skip_len(" ", true), Some(1));
+        assert_eq!(Recovery::Abort.skip_len(input, false), Some(1));
+    }
+}
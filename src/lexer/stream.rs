@@ -0,0 +1,476 @@
+use std::io::{self, Read};
+use std::marker::PhantomData;
+
+use crate::{
+    dfa::{PartialMatch, TokenDFA},
+    language::{Language, LanguageError, Match},
+    nfa::{NFASet, NFA},
+};
+
+use super::{
+    token::{OwnedSpanned, Token},
+    LexError, Recovery,
+};
+
+/// Number of bytes read from the source at a time.
+const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Provides the input for a [`StreamLexer`].
+pub trait Source {
+    /// Append more input to `buf`, reading roughly `hint` bytes.
+    /// Returns the number of appended bytes, `Ok(0)` when the input has ended.
+    fn fill(&mut self, buf: &mut String, hint: usize) -> io::Result<usize>;
+}
+
+/// Reads UTF-8 input from a [`Read`].
+#[derive(Debug)]
+pub struct ReadSource<R> {
+    reader: R,
+    /// Bytes of an incomplete char at the end of the last read.
+    pending: Vec<u8>,
+}
+
+impl<R: Read> ReadSource<R> {
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: vec![],
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+impl<R: Read> Source for ReadSource<R> {
+    fn fill(&mut self, buf: &mut String, hint: usize) -> io::Result<usize> {
+        let mut chunk = vec![0; hint.max(4)];
+
+        loop {
+            let read = match self.reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            if read == 0 {
+                return if self.pending.is_empty() {
+                    Ok(0)
+                } else {
+                    Err(invalid_utf8())
+                };
+            }
+
+            self.pending.extend_from_slice(&chunk[..read]);
+            let valid = match std::str::from_utf8(&self.pending) {
+                Ok(s) => s.len(),
+                // The last char continues in the next read
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => return Err(invalid_utf8()),
+            };
+
+            if valid > 0 {
+                let s = std::str::from_utf8(&self.pending[..valid]).map_err(|_| invalid_utf8())?;
+                buf.push_str(s);
+                self.pending.drain(..valid);
+                return Ok(valid);
+            }
+        }
+    }
+}
+
+/// Reads input from an iterator of chars.
+#[derive(Debug)]
+pub struct CharSource<I> {
+    chars: I,
+}
+
+impl<I: Iterator<Item = char>> CharSource<I> {
+    #[must_use]
+    pub fn new(chars: I) -> Self {
+        Self { chars }
+    }
+}
+
+impl<I: Iterator<Item = char>> Source for CharSource<I> {
+    fn fill(&mut self, buf: &mut String, hint: usize) -> io::Result<usize> {
+        let len = buf.len();
+        for c in self.chars.by_ref() {
+            buf.push(c);
+            if buf.len() - len >= hint {
+                break;
+            }
+        }
+        Ok(buf.len() - len)
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Lex(LexError),
+    Io(io::Error),
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lex(e) => e.fmt(f),
+            Self::Io(e) => write!(f, "Could not read input: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<LexError> for StreamError {
+    fn from(e: LexError) -> Self {
+        Self::Lex(e)
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// The part of the input which is kept in memory.
+#[derive(Debug)]
+struct Window<S> {
+    source: S,
+    buffer: String,
+    /// Bytes of `buffer` which have been lexed.
+    pos: usize,
+    /// Offset of `buffer` in the complete input.
+    offset: usize,
+    chunk_size: usize,
+    /// True when the source has no more input.
+    done: bool,
+}
+
+impl<S: Source> Window<S> {
+    #[must_use]
+    fn remaining(&self) -> &str {
+        &self.buffer[self.pos..]
+    }
+
+    /// Read more input, dropping the part which has been lexed.
+    fn refill(&mut self) -> io::Result<()> {
+        self.buffer.drain(..self.pos);
+        self.offset += self.pos;
+        self.pos = 0;
+
+        if self.source.fill(&mut self.buffer, self.chunk_size)? == 0 {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// Longest match of `dfa` at the current position, reads more input until the match is known.
+    fn find(&mut self, dfa: &TokenDFA) -> io::Result<Option<Match>> {
+        // Continues after each refill, the input of a long token is only read once
+        let mut scan = dfa.start_scan();
+        loop {
+            match dfa.resume(&mut scan, self.remaining(), self.done) {
+                PartialMatch::Complete(m) => return Ok(m),
+                PartialMatch::Incomplete => self.refill()?,
+            }
+        }
+    }
+}
+
+/// A [`super::Lexer`] which reads the input from a [`Source`] as needed,
+/// so the complete input never has to be in memory.
+///
+/// Only the current token, and the input read after it, is kept in memory.
+/// The offsets in the returned tokens are counted from the start of the complete input.
+#[derive(Debug)]
+pub struct StreamLexer<S, T> {
+    window: Window<S>,
+    /// Compiled [`Token::get_skip_reg`].
+    skip: TokenDFA,
+    phantom: PhantomData<T>,
+    recovery: Recovery,
+    /// True when the input is empty and a `T::eof()` token has been returned.
+    sent_eof: bool,
+    /// True when the source failed or the lexer aborted, the iterator only produces `None` afterwards.
+    sent_error: bool,
+}
+
+impl<R: Read, T: Token> StreamLexer<ReadSource<R>, T> {
+    /// # Errors
+    ///
+    /// Fails if [`Token::get_skip_reg`] is not a valid pattern.
+    pub fn from_reader(reader: R) -> Result<Self, LanguageError> {
+        Self::new(ReadSource::new(reader))
+    }
+}
+
+impl<I: Iterator<Item = char>, T: Token> StreamLexer<CharSource<I>, T> {
+    /// # Errors
+    ///
+    /// Fails if [`Token::get_skip_reg`] is not a valid pattern.
+    pub fn from_chars(chars: impl IntoIterator<IntoIter = I>) -> Result<Self, LanguageError> {
+        Self::new(CharSource::new(chars.into_iter()))
+    }
+}
+
+impl<S: Source, T: Token> StreamLexer<S, T> {
+    /// # Errors
+    ///
+    /// Fails if [`Token::get_skip_reg`] is not a valid pattern.
+    pub fn new(source: S) -> Result<Self, LanguageError> {
+        Self::with_chunk_size(source, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Read roughly `chunk_size` bytes from `source` at a time.
+    ///
+    /// # Errors
+    ///
+    /// Fails if [`Token::get_skip_reg`] is not a valid pattern.
+    pub fn with_chunk_size(source: S, chunk_size: usize) -> Result<Self, LanguageError> {
+        let skip = NFA::try_from_language(T::get_skip_reg())?;
+        let skip = NFASet::build(vec![("skip".into(), skip)])
+            .expect("A single NFA is provided")
+            .determinize();
+
+        Ok(Self {
+            window: Window {
+                source,
+                buffer: String::new(),
+                pos: 0,
+                offset: 0,
+                chunk_size: chunk_size.max(1),
+                done: false,
+            },
+            skip,
+            phantom: PhantomData,
+            recovery: Recovery::default(),
+            sent_eof: false,
+            sent_error: false,
+        })
+    }
+
+    /// Set what to do after input which doesn't match any token,
+    /// the default is to skip one char.
+    #[must_use]
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    fn lex(&mut self) -> Result<Option<OwnedSpanned<T>>, StreamError> {
+        if let Some(m) = self.window.find(&self.skip)? {
+            self.window.pos += m.match_size();
+        }
+
+        let start = self.window.offset + self.window.pos;
+
+        // `find` only stops at the end of the buffer when the source is done
+        if self.window.remaining().is_empty() {
+            self.sent_eof = true;
+            return Ok(T::eof().map(|token| OwnedSpanned {
+                start,
+                token,
+                text: String::new(),
+                end: start,
+            }));
+        }
+
+        match self.window.find(T::get_token_dfa())? {
+            Some(Match::Group(label, size)) => {
+                let text = &self.window.remaining()[..size];
+                let token = T::token_from_match(label.into(), text);
+                let text = text.to_string();
+                self.window.pos += size;
+
+                Ok(Some(OwnedSpanned {
+                    start,
+                    token,
+                    text,
+                    end: start + size,
+                }))
+            }
+            Some(Match::NoGroup(_)) => {
+                unreachable!("All matches from NFASet should have a group")
+            }
+            None => {
+                let skip = loop {
+                    match self
+                        .recovery
+                        .skip_len(self.window.remaining(), self.window.done)
+                    {
+                        Some(skip) => break skip,
+                        None => self.window.refill()?,
+                    }
+                };
+                self.window.pos += skip;
+
+                if self.recovery == Recovery::Abort {
+                    self.sent_error = true;
+                }
+                Err(LexError::UnrecognizedToken(start).into())
+            }
+        }
+    }
+}
+
+impl<S: Source, T: Token> Iterator for StreamLexer<S, T> {
+    type Item = Result<OwnedSpanned<T>, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.sent_error || self.sent_eof {
+            return None;
+        }
+
+        match self.lex() {
+            Ok(token) => token.map(Ok),
+            Err(e) => {
+                if matches!(e, StreamError::Io(_)) {
+                    self.sent_error = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        tests::{CommentToken, DeriveToken, ExprToken},
+        Lexer,
+    };
+    use super::*;
+    use crate::impl_token;
+    use lazy_static::lazy_static;
+
+    /// Returns at most one byte for each read.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((b, rest)), Some(out)) => {
+                    *out = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    fn lex_str<T: Token + std::fmt::Debug>(input: &str) -> Vec<Result<OwnedSpanned<T>, LexError>> {
+        Lexer::<T>::new(input)
+            .map(|res| res.map(OwnedSpanned::from))
+            .collect()
+    }
+
+    fn lex_stream<S: Source, T: Token>(
+        lexer: StreamLexer<S, T>,
+    ) -> Vec<Result<OwnedSpanned<T>, LexError>> {
+        lexer
+            .map(|res| {
+                res.map_err(|e| match e {
+                    StreamError::Lex(e) => e,
+                    StreamError::Io(e) => panic!("{e}"),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_as_lexer() {
+        let input = "one1+two2 - 1 +21 a20 / \u{e5}\u{1F600} 3 ".repeat(20);
+
+        let expected = lex_str::<ExprToken>(&input);
+        assert_eq!(
+            lex_stream(
+                StreamLexer::<_, ExprToken>::from_reader(Trickle(input.as_bytes())).unwrap()
+            ),
+            expected
+        );
+        assert_eq!(
+            lex_stream(StreamLexer::<_, ExprToken>::from_reader(input.as_bytes()).unwrap()),
+            expected
+        );
+        assert_eq!(
+            lex_stream(
+                StreamLexer::<_, ExprToken>::with_chunk_size(CharSource::new(input.chars()), 3)
+                    .unwrap()
+            ),
+            expected
+        );
+
+        for recovery in [
+            Recovery::SkipChars(3),
+            Recovery::SkipToWhitespace,
+            Recovery::Abort,
+        ] {
+            let lexer = StreamLexer::<_, ExprToken>::from_reader(Trickle(input.as_bytes()))
+                .unwrap()
+                .with_recovery(recovery);
+            let expected = Lexer::<ExprToken>::new(&input)
+                .with_recovery(recovery)
+                .map(|res| res.map(OwnedSpanned::from))
+                .collect::<Vec<_>>();
+            assert_eq!(lex_stream(lexer), expected, "{recovery:?}");
+        }
+
+        let input = "// comment \u{e5}\r\nabc 12\t// 34\n\r\n  // x y\n5 // end";
+        assert_eq!(
+            lex_stream(
+                StreamLexer::<_, CommentToken>::from_reader(Trickle(input.as_bytes())).unwrap()
+            ),
+            lex_str::<CommentToken>(input)
+        );
+
+        let input = "let x1 =\r\n 42 letter";
+        let tokens = lex_stream(StreamLexer::<_, DeriveToken>::from_chars(input.chars()).unwrap());
+        assert_eq!(tokens, lex_str::<DeriveToken>(input));
+        assert_eq!(
+            tokens.last(),
+            Some(&Ok(OwnedSpanned {
+                start: 20,
+                token: DeriveToken::Eof,
+                text: String::new(),
+                end: 20
+            }))
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut lexer = StreamLexer::<_, ExprToken>::from_reader(Trickle(b"1 + \xff 2")).unwrap();
+        assert!(matches!(lexer.next(), Some(Ok(_))));
+        assert!(matches!(lexer.next(), Some(Ok(_))));
+        assert!(matches!(lexer.next(), Some(Err(StreamError::Io(_)))));
+        assert!(lexer.next().is_none());
+
+        // Truncated char at the end
+        let mut lexer = StreamLexer::<_, ExprToken>::from_reader(Trickle(b"1 \xc3")).unwrap();
+        assert!(matches!(lexer.next(), Some(Ok(_))));
+        assert!(matches!(lexer.next(), Some(Err(StreamError::Io(_)))));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum UnclosedSkip {
+        Num,
+    }
+
+    impl_token!(UnclosedSkip, None, skip = r"(\ ", (Num, "num", r"(0-9)+"));
+
+    #[test]
+    fn invalid_skip() {
+        assert!(matches!(
+            StreamLexer::<_, UnclosedSkip>::from_chars("1".chars()),
+            Err(LanguageError::ParseError(_))
+        ));
+    }
+}
@@ -1,78 +1,115 @@
-use lazy_static::lazy_static;
-
-use crate::{
-    language::{Language, Match, self},
-    nfa::{NFASet, NFA},
-};
+use crate::{dfa::TokenDFA, language::Match, nfa::NFASet};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Spanned<T> {
+pub struct Spanned<'input, T> {
+    pub start: usize,
+    pub token: T,
+    /// The matched part of the input, `&input[start..end]`.
+    pub text: &'input str,
+    pub end: usize,
+}
+
+/// Same as [`Spanned`] but owns the matched text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedSpanned<T> {
     pub start: usize,
     pub token: T,
+    pub text: String,
     pub end: usize,
 }
 
+impl<T> From<Spanned<'_, T>> for OwnedSpanned<T> {
+    fn from(value: Spanned<'_, T>) -> Self {
+        Self {
+            start: value.start,
+            token: value.token,
+            text: value.text.to_string(),
+            end: value.end,
+        }
+    }
+}
+
 pub trait Token
 where
     Self: Sized,
 {
+    /// Find the longest token at the start of `input`.
+    /// Ties are won by the rule declared first.
     #[must_use]
     fn next_match(input: &str) -> Option<(usize, Self)> {
-        // Find longest match
-        let m = Self::get_token_set()
-            .is_match(input)
-            .into_iter()
-            .max_by_key(language::Match::match_size);
-
-        if let Some(m) = m {
-            match m {
-                Match::Group(label, size) => Some((size, Self::token_from_label(label.into()))),
-                Match::NoGroup(_) => {
-                    unreachable!("All matches from NFASet should have a group")
-                }
+        match Self::get_token_dfa().longest_match(input)? {
+            Match::Group(label, size) => {
+                Some((size, Self::token_from_match(label.into(), &input[..size])))
+            }
+            Match::NoGroup(_) => {
+                unreachable!("All matches from NFASet should have a group")
             }
-        } else {
-            None
         }
     }
 
+    /// Length of the input to skip at the start of `input`, see [`Token::get_skip_reg`].
     #[must_use]
     fn skip_chars(input: &str) -> usize {
-        Self::skip_reg()
-            .is_match(input)
-            .into_iter()
-            .map(|m| m.match_size())
-            .max()
-            .unwrap_or(0)
+        Self::get_skip_dfa()
+            .longest_match(input)
+            .map_or(0, |m| m.match_size())
     }
 
+    /// Compiled [`Token::get_skip_reg`], with the single rule `"skip"`.
     #[must_use]
-    fn skip_reg() -> &'static NFA {
-        lazy_static! {
-            static ref SKIP_REG: NFA = NFA::try_from_language(r"(\n|\t|\ )*").unwrap();
-        }
-        &SKIP_REG
-    }
+    fn get_skip_dfa() -> &'static TokenDFA;
 
     #[must_use]
     fn eof() -> Option<Self>;
 
+    /// Pattern for the input skipped between tokens, e.g. whitespace and comments.
     #[must_use]
     fn get_skip_reg() -> &'static str;
 
     #[must_use]
     fn get_token_set() -> &'static NFASet;
 
+    /// Compiled [`Token::get_token_set`].
+    #[must_use]
+    fn get_token_dfa() -> &'static TokenDFA;
+
+    /// Create the token for the rule `label` which matched `text`.
     #[must_use]
-    fn token_from_label(label: &'static str) -> Self;
+    fn token_from_match(label: &'static str, text: &str) -> Self;
 }
 
+/// Implement [`Token`] for an enum.
+///
+/// Prefer `#[derive(Token)]` which validates the patterns at compile time.
+///
+/// Each rule is given as `(Variant, "label", r"pattern")`, the labels must be unique.
+/// The longest match wins, rules declared first win when two rules match the same length.
+/// Variants with data are constructed from the matched text by adding a converter to the rule,
+/// `(Num(i64), "num", r"(0-9)+", |s| Num(s.parse().unwrap()))`.
+/// Whitespace, `(\n|\t|\ )*`, is skipped between tokens unless a skip pattern is provided:
+///
+/// ```ignore
+/// impl_token!(
+///     ExprToken,
+///     None,
+///     skip = r"(\n|\t|\r|\ |//(\ -~|\t|\r)*\n)*",
+///     (Num, "num", r"(0-9)+")
+/// );
+/// ```
 #[macro_export]
 macro_rules! impl_token {
+    (@construct $variant:expr, $text:ident) => {
+        $variant
+    };
+    (@construct $variant:expr, $text:ident, $convert:expr) => {{
+        let convert: fn(&str) -> Self = $convert;
+        convert($text)
+    }};
     (
         $this:ident,
         $eof:expr,
-        $(($variant:expr, $label:expr, $regex:expr)),+
+        skip = $skip:expr,
+        $(($variant:expr, $label:expr, $regex:expr $(, $convert:expr)?)),+
     ) => {
         impl Token for $this {
             fn eof() -> Option<Self> {
@@ -80,7 +117,19 @@ macro_rules! impl_token {
             }
 
             fn get_skip_reg() -> &'static str {
-                r"(\n|\t|\ )*"
+                $skip
+            }
+
+            fn get_skip_dfa() -> &'static TokenDFA {
+                lazy_static! {
+                    static ref SKIP_DFA: TokenDFA = NFASet::build(vec![(
+                        "skip".into(),
+                        NFA::try_from_language($skip).unwrap()
+                    )])
+                    .unwrap()
+                    .determinize();
+                }
+                &SKIP_DFA
             }
 
             fn get_token_set() -> &'static NFASet {
@@ -93,13 +142,35 @@ macro_rules! impl_token {
                 &TOKEN_SET
             }
 
-            fn token_from_label(label: &'static str) -> Self {
+            fn get_token_dfa() -> &'static TokenDFA {
+                lazy_static! {
+                    static ref TOKEN_DFA: TokenDFA =
+                        <$this as Token>::get_token_set().determinize();
+                }
+                &TOKEN_DFA
+            }
+
+            // `text` is unused when no rule has a converter
+            #[allow(unused_variables)]
+            fn token_from_match(label: &'static str, text: &str) -> Self {
                 use $this::*;
                 match label {
-                    $($label => $variant,)+
+                    $($label => $crate::impl_token!(@construct $variant, text $(, $convert)?),)+
                     _ => unreachable!("No mapping for group: {label}"),
                 }
             }
         }
     };
+    (
+        $this:ident,
+        $eof:expr,
+        $(($variant:expr, $label:expr, $regex:expr $(, $convert:expr)?)),+
+    ) => {
+        $crate::impl_token!(
+            $this,
+            $eof,
+            skip = r"(\n|\t|\ )*",
+            $(($variant, $label, $regex $(, $convert)?)),+
+        );
+    };
 }
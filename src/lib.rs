@@ -1,10 +1,29 @@
-#![feature(test)]
+//! The matching core in [`parse`], [`nfa`], [`dfa`] and [`language`] only needs `alloc`.
+//! Everything else requires the default `std` feature.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+// Allows the `Token` derive to refer to `::automata_rust` inside this crate.
+extern crate self as automata_rust;
+
+mod collections;
 mod table;
 
+#[cfg(feature = "std")]
+pub mod cfg;
+#[cfg(feature = "std")]
+pub mod codegen;
 pub mod dfa;
+#[cfg(feature = "std")]
 pub mod graph_display;
-pub mod nfa;
-pub mod parse;
 pub mod language;
+#[cfg(feature = "std")]
 pub mod lexer;
+pub mod nfa;
+pub use automata_rust_parse as parse;
+#[cfg(feature = "std")]
+pub mod pda;
+#[cfg(feature = "std")]
+pub mod transducer;
@@ -2,7 +2,13 @@ use std::process::ExitCode;
 
 use clap::{Parser, Subcommand};
 
-use automata_rust::{self, graph_display::DiGraph, language::Language};
+use automata_rust::{
+    self,
+    dfa::DFA,
+    graph_display::DiGraph,
+    language::{Language, LanguageError},
+    nfa::NFA,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -18,6 +24,18 @@ enum Commands {
         nfa: bool,
         #[arg(long)]
         dfa: bool,
+        /// Remove unreachable and dead states from the NFA
+        #[arg(long)]
+        simplify: bool,
+        /// Draw the NFA without eps-transitions
+        #[arg(long)]
+        epsilon_free: bool,
+        /// Use the built-in layout instead of Graphviz
+        #[arg(long)]
+        builtin: bool,
+        /// Abort if the DFA needs more states than this
+        #[arg(long, default_value_t = DFA::DEFAULT_MAX_STATES)]
+        max_states: usize,
         input: String,
     },
     Table {
@@ -38,26 +56,55 @@ fn main() -> ExitCode {
     }
 }
 
+/// Compile `input`, parse errors point at the offending part of the pattern.
+fn compile(input: &str) -> Result<NFA, Box<dyn std::error::Error>> {
+    NFA::try_from_language(input).map_err(|e| match e {
+        LanguageError::ParseError(e) => e.render(input).into(),
+        e => e.into(),
+    })
+}
+
 fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     let mut svg = None;
     let mut table = None;
 
     match args.command {
-        Commands::Svg { nfa, dfa, input } => {
-            if nfa {
-                let nfa = automata_rust::nfa::NFA::try_from_language(input)?;
-                let graph: DiGraph = (&nfa).into();
-                svg = Some(graph.to_string());
+        Commands::Svg {
+            nfa,
+            dfa,
+            simplify,
+            epsilon_free,
+            builtin,
+            max_states,
+            input,
+        } => {
+            let graph: Option<DiGraph> = if nfa {
+                let mut nfa = compile(&input)?;
+                if simplify {
+                    nfa = nfa.remove_unreachable().remove_dead();
+                }
+                if epsilon_free {
+                    Some((&nfa.eliminate_epsilon()).into())
+                } else {
+                    Some((&nfa).into())
+                }
             } else if dfa {
-                let nfa = automata_rust::nfa::NFA::try_from_language(input)?;
-                let dfa = automata_rust::dfa::DFA::from(nfa);
-                let graph: DiGraph = (&dfa).into();
-                svg = Some(graph.to_string());
-            }
+                let nfa = compile(&input)?;
+                let dfa = DFA::determinize(&nfa, max_states)?;
+                Some((&dfa).into())
+            } else {
+                None
+            };
+
+            svg = match graph {
+                Some(graph) if builtin => Some(graph.to_svg()),
+                Some(graph) => Some(graph.render()?),
+                None => None,
+            };
         }
         Commands::Table { nfa, input } => {
             if nfa {
-                table = Some(automata_rust::nfa::NFA::try_from_language(input)?.to_string());
+                table = Some(compile(&input)?.to_string());
             } else {
                 return Err("Exactly one graph representation must be chosen!".into());
             }
@@ -0,0 +1,204 @@
+//! A NFA without eps-transitions, see [`NFA::eliminate_epsilon`].
+
+use alloc::{vec, vec::Vec};
+
+use super::{State, Transition, NFA};
+
+use crate::{
+    collections::HashMap,
+    dfa::lazy_closure,
+    language::{Label, Match},
+    parse::Lit,
+};
+
+/// A NFA where every transition consumes a char.
+///
+/// Each state has any number of transitions, and may accept for several groups.
+#[derive(Debug)]
+pub struct EpsilonFreeNFA {
+    /// `transitions[state]` are the chars accepted in `state` and the states they lead to.
+    pub transitions: Vec<Vec<(Lit, State)>>,
+    pub start: State,
+    /// The groups accepting in each state.
+    pub accept: Vec<Vec<Option<Label>>>,
+    /// The groups accepting in each state if the whole input has been consumed,
+    /// i.e. patterns ending with `$`.
+    pub accept_eof: Vec<Vec<Option<Label>>>,
+    /// The group each state belongs to, `None` for the start state.
+    pub groups: Vec<Option<Label>>,
+    /// States reached by a label which is only reached through a lazy branch, see [`NFA::lazy`].
+    /// They are not entered once their group has matched.
+    pub lazy: Vec<bool>,
+}
+
+impl NFA {
+    /// Build an equivalent NFA without eps-transitions.
+    ///
+    /// A state is created for the start state and for each state reached by a label,
+    /// together with the group it was reached from and if the label is only reached lazily.
+    #[must_use]
+    pub fn eliminate_epsilon(&self) -> EpsilonFreeNFA {
+        let mut ids = HashMap::from([((None, self.start, false), State(0))]);
+        let mut states = vec![(None, self.start, false)];
+        let mut nfa = EpsilonFreeNFA {
+            transitions: vec![],
+            start: State(0),
+            accept: vec![],
+            accept_eof: vec![],
+            groups: vec![],
+            lazy: vec![],
+        };
+
+        // `states` grows while we iterate over it
+        while nfa.transitions.len() < states.len() {
+            let mut transitions = vec![];
+            let mut accept = vec![];
+            let mut accept_eof = vec![];
+
+            let (group, state, lazy) = states[nfa.transitions.len()];
+            nfa.groups.push(group);
+            nfa.lazy.push(lazy);
+
+            for ((group, state), lazy) in lazy_closure(self, vec![(group, state)]) {
+                match &self[state] {
+                    Transition::Label(lit, e) => {
+                        let key = (group, *e, lazy);
+                        let next = *ids.entry(key).or_insert_with(|| {
+                            states.push(key);
+                            State(states.len() - 1)
+                        });
+                        transitions.push((lit.clone(), next));
+                    }
+                    Transition::Accept => accept.push(group),
+                    Transition::Eof => accept_eof.push(group),
+                    Transition::Split(_, _) | Transition::Group(_, _) => unreachable!(),
+                }
+            }
+
+            // The accept state may be reached both lazily and not
+            accept.dedup();
+            nfa.transitions.push(transitions);
+            nfa.accept.push(accept);
+            nfa.accept_eof.push(accept_eof);
+        }
+
+        nfa
+    }
+}
+
+impl EpsilonFreeNFA {
+    /// Returns the longest match for each group, same as [`NFA::is_match`](crate::language::Language::is_match).
+    #[must_use]
+    pub fn is_match(&self, input: &str) -> Vec<Match> {
+        let mut matches = HashMap::new();
+        let mut current = vec![self.start];
+        let mut next = vec![];
+        let mut visited = vec![false; self.transitions.len()];
+
+        let mut matched = vec![];
+
+        for (i, c) in input.char_indices() {
+            matched.clear();
+            for &state in &current {
+                matched.extend(&self.accept[state]);
+            }
+            matches.extend(matched.iter().map(|&group| (group, i)));
+
+            visited.fill(false);
+            for &state in &current {
+                for &(ref lit, e) in &self.transitions[state] {
+                    // Lazy quantifiers stop as soon as their group has matched
+                    let stopped = self.lazy[e.0] && matched.contains(&self.groups[e.0]);
+                    if lit.accepts(c) && !visited[e.0] && !stopped {
+                        visited[e.0] = true;
+                        next.push(e);
+                    }
+                }
+            }
+
+            core::mem::swap(&mut current, &mut next);
+            next.clear();
+            if current.is_empty() {
+                break;
+            }
+        }
+
+        // `current` is only non-empty here if the whole input was read
+        for &state in &current {
+            let accept = self.accept[state].iter().chain(&self.accept_eof[state]);
+            matches.extend(accept.map(|&group| (group, input.len())));
+        }
+
+        matches.into_iter().map(Match::from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{language::Language, nfa::NFASet};
+
+    /// The longest match for each group.
+    fn longest(matches: Vec<Match>) -> BTreeMap<Option<&'static str>, usize> {
+        let mut longest = BTreeMap::new();
+        for m in matches {
+            let (group, size) = match m {
+                Match::Group(label, size) => (Some(label.into()), size),
+                Match::NoGroup(size) => (None, size),
+            };
+            let entry = longest.entry(group).or_insert(size);
+            *entry = size.max(*entry);
+        }
+        longest
+    }
+
+    #[test]
+    fn eliminate_epsilon() {
+        let inputs = [
+            "", "a", "ab", "abab", "aab", "abc", "b", "ba", "x1", "ab$", "abaab",
+        ];
+
+        for pattern in [
+            "ab",
+            "(ab)*",
+            "a?b+|ba",
+            "(a-z)(a-z|0-9)*",
+            "(a|b)*c$",
+            "a*",
+            "(a|b)*?b",
+            "a+?b*",
+            "(a*?b)*",
+            "(a*?b)+",
+        ] {
+            let nfa = NFA::try_from_language(pattern).unwrap();
+            let free = nfa.eliminate_epsilon();
+            assert!(free.transitions.len() < nfa.transitions.len());
+
+            for input in inputs {
+                assert_eq!(
+                    longest(free.is_match(input)),
+                    longest(nfa.is_match(input)),
+                    "Pattern: {pattern}, input: {input}"
+                );
+            }
+        }
+
+        let set = NFASet::build(vec![
+            ("ab".into(), NFA::try_from_language("ab").unwrap()),
+            ("a".into(), NFA::try_from_language("a+").unwrap()),
+            ("end".into(), NFA::try_from_language("(a|b)*$").unwrap()),
+            ("lazy".into(), NFA::try_from_language("a(a|b)*?b").unwrap()),
+        ])
+        .unwrap();
+        let free = set.nfa.eliminate_epsilon();
+        for input in inputs {
+            assert_eq!(
+                longest(free.is_match(input)),
+                longest(set.nfa.is_match(input)),
+                "Input: {input}"
+            );
+        }
+    }
+}
@@ -1,7 +1,10 @@
+mod epsilon_free;
+#[allow(clippy::module_inception)]
 mod nfa;
 mod nfa_set;
 mod state;
 
-pub use nfa::{NFA, Transition};
+pub use epsilon_free::EpsilonFreeNFA;
+pub use nfa::{Transition, NFA};
 pub use nfa_set::NFASet;
 pub use state::State;
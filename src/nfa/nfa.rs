@@ -7,19 +7,22 @@
 //! <https://swtch.com/~rsc/regexp/regexp1.html>
 //!
 
-#![allow(soft_unstable)]
-
-use std::collections::{HashMap, HashSet};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 
 use super::state::State;
 
 use crate::{
+    collections::{HashMap, HashSet},
     language::{CompileError, Label, Language, LanguageError, Match},
     parse::{Lit, Postfix, Token},
     table::Table,
 };
 
-impl<T> std::ops::Index<State> for Vec<T> {
+impl<T> core::ops::Index<State> for Vec<T> {
     type Output = T;
 
     fn index(&self, index: State) -> &Self::Output {
@@ -27,7 +30,7 @@ impl<T> std::ops::Index<State> for Vec<T> {
     }
 }
 
-impl<T> std::ops::IndexMut<State> for Vec<T> {
+impl<T> core::ops::IndexMut<State> for Vec<T> {
     fn index_mut(&mut self, index: State) -> &mut Self::Output {
         &mut self[index.0]
     }
@@ -52,6 +55,9 @@ pub struct NFA {
     pub accept: State,
     /// State that don't accept any more tokens.
     pub eof: State,
+    /// Splits whose first branch enters or repeats a lazy quantifier.
+    /// The states only reached through these branches are dropped once their group has matched.
+    pub lazy: HashSet<State>,
 }
 
 impl NFA {
@@ -64,11 +70,18 @@ impl NFA {
             accept: State(0),
             // Is changed when regex is compiled
             start: State(0),
+            lazy: HashSet::new(),
         }
     }
 }
 
-impl std::ops::Index<State> for NFA {
+impl Default for NFA {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::ops::Index<State> for NFA {
     type Output = Transition;
 
     fn index(&self, index: State) -> &Self::Output {
@@ -76,7 +89,7 @@ impl std::ops::Index<State> for NFA {
     }
 }
 
-impl std::ops::IndexMut<State> for NFA {
+impl core::ops::IndexMut<State> for NFA {
     fn index_mut(&mut self, index: State) -> &mut Self::Output {
         &mut self.transitions[index]
     }
@@ -121,6 +134,34 @@ impl NFA {
             }
         }
     }
+
+    /// Returns true if the accept state is reached from `state` without reading input.
+    fn reaches_accept(&self, state: State) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![state];
+        while let Some(state) = stack.pop() {
+            if state == self.accept {
+                return true;
+            }
+            if visited.insert(state) {
+                if let Transition::Split(_, _) | Transition::Group(_, _) = self[state] {
+                    stack.extend(self[state].next().into_iter().flatten());
+                }
+            }
+        }
+        false
+    }
+}
+
+impl Transition {
+    /// The states this transition leads to.
+    pub(crate) fn next(&self) -> [Option<State>; 2] {
+        match *self {
+            Self::Label(_, e) | Self::Group(_, e) => [Some(e), None],
+            Self::Split(e1, e2) => [e1, e2],
+            Self::Accept | Self::Eof => [None, None],
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -141,6 +182,8 @@ impl NFA {
         nfa.accept = nfa.new_accept_state();
 
         let mut stack: Vec<Frag> = vec![];
+        // Splits which skip a lazy quantifier
+        let mut skips = vec![];
 
         for tok in postfix.tokens {
             match tok {
@@ -150,9 +193,7 @@ impl NFA {
                     // s <----
                     //  \
                     //   -------->
-                    let e = stack.pop().ok_or(CompileError::EmptyStack {
-                        token: Token::KleeneS,
-                    })?;
+                    let e = stack.pop().ok_or(CompileError::EmptyStack { token: tok })?;
                     let s = nfa.new_split_state(Some(e.start), None);
                     nfa.patch(&e, s);
                     let e = Frag {
@@ -183,7 +224,7 @@ impl NFA {
                         out: e2.out,
                     });
                 }
-                Token::KleeneP => {
+                Token::KleeneP | Token::LazyKleeneP | Token::LazyKleeneS => {
                     //  -----
                     // /    |
                     // v    |
@@ -191,13 +232,23 @@ impl NFA {
                     let e = stack.pop().unwrap();
                     let s = nfa.new_split_state(Some(e.start), None);
                     nfa.patch(&e, s);
-                    let e = Frag {
+                    if tok != Token::KleeneP {
+                        nfa.lazy.insert(s);
+                    }
+                    let mut e = Frag {
                         start: e.start,
                         out: vec![s],
                     };
+                    if tok == Token::LazyKleeneS {
+                        // Built as `(e+?)??`, entering the loop is lazy separately from repeating it
+                        let s = nfa.new_split_state(Some(e.start), None);
+                        e.out.push(s);
+                        e.start = s;
+                        skips.push(s);
+                    }
                     stack.push(e);
                 }
-                Token::Optional => {
+                Token::Optional | Token::LazyOptional => {
                     //   -> e --\
                     //  /        v
                     // s
@@ -207,6 +258,9 @@ impl NFA {
                     let s = nfa.new_split_state(Some(e.start), None);
                     e.out.push(s);
                     e.start = s;
+                    if tok == Token::LazyOptional {
+                        skips.push(s);
+                    }
                     stack.push(e);
                 }
                 Token::Range => {
@@ -242,6 +296,15 @@ impl NFA {
         if let (1, Some(e)) = (stack.len(), stack.pop()) {
             nfa.start = e.start;
             nfa.patch(&e, nfa.accept);
+
+            // Entering a lazy quantifier is only lazy if skipping it matches without reading input
+            for s in skips {
+                if let Transition::Split(_, Some(skip)) = nfa[s] {
+                    if nfa.reaches_accept(skip) {
+                        nfa.lazy.insert(s);
+                    }
+                }
+            }
             Ok(nfa)
         } else {
             Err(CompileError::NonUnaryStack { size: stack.len() })
@@ -305,10 +368,8 @@ impl NFA {
                     }
                     states.push(*e);
                 }
-                &Transition::Split(e1, e2) => {
-                    if e1.is_some() | e2.is_some() {
-                        return false;
-                    }
+                &Transition::Split(e1, e2) if e1.is_some() | e2.is_some() => {
+                    return false;
                 }
                 _ => {}
             }
@@ -318,6 +379,118 @@ impl NFA {
     }
 }
 
+impl NFA {
+    /// Remove the states which can't be reached from the start state.
+    ///
+    /// The accept state is always kept. If the eof state is removed, `eof` is set to `accept`.
+    #[must_use]
+    pub fn remove_unreachable(&self) -> Self {
+        let mut keep = vec![false; self.transitions.len()];
+        keep[self.start] = true;
+        keep[self.accept] = true;
+        let mut stack = vec![self.start];
+
+        while let Some(state) = stack.pop() {
+            for next in self[state].next().into_iter().flatten() {
+                if !keep[next] {
+                    keep[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+
+        self.retain(keep)
+    }
+
+    /// Remove the states which can't reach the accept or eof state.
+    ///
+    /// Splits leading to removed states are kept with only the remaining branch.
+    #[must_use]
+    pub fn remove_dead(&self) -> Self {
+        let mut previous = vec![vec![]; self.transitions.len()];
+        for (state, transition) in self.transitions.iter().enumerate() {
+            for next in transition.next().into_iter().flatten() {
+                previous[next.0].push(State(state));
+            }
+        }
+
+        let mut stack = (0..self.transitions.len())
+            .map(State)
+            .filter(|&s| matches!(self[s], Transition::Accept | Transition::Eof))
+            .collect::<Vec<_>>();
+        let mut keep = vec![false; self.transitions.len()];
+        for &state in &stack {
+            keep[state] = true;
+        }
+
+        while let Some(state) = stack.pop() {
+            for &prev in &previous[state.0] {
+                if !keep[prev] {
+                    keep[prev] = true;
+                    stack.push(prev);
+                }
+            }
+        }
+
+        if !keep[self.start] {
+            // Nothing is accepted
+            return Self {
+                transitions: vec![Transition::Split(None, None), Transition::Accept],
+                start: State(0),
+                accept: State(1),
+                eof: State(1),
+                lazy: HashSet::new(),
+            };
+        }
+
+        keep[self.accept] = true;
+        self.retain(keep)
+    }
+
+    /// Keep the states in `keep` and renumber them.
+    /// Splits leading to removed states are dropped,
+    /// all other transitions must lead to kept states.
+    fn retain(&self, keep: Vec<bool>) -> Self {
+        let mut ids = vec![None; self.transitions.len()];
+        let mut count = 0;
+        for (state, keep) in keep.iter().enumerate() {
+            if *keep {
+                ids[state] = Some(State(count));
+                count += 1;
+            }
+        }
+        let id = |state: State| ids[state].expect("The state is kept");
+
+        let transitions = self
+            .transitions
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(transition, _)| match transition {
+                Transition::Label(l, e) => Transition::Label(l.clone(), id(*e)),
+                &Transition::Split(e1, e2) => {
+                    Transition::Split(e1.and_then(|e| ids[e]), e2.and_then(|e| ids[e]))
+                }
+                Transition::Group(g, e) => Transition::Group(*g, id(*e)),
+                Transition::Accept => Transition::Accept,
+                Transition::Eof => Transition::Eof,
+            })
+            .collect();
+
+        let accept = id(self.accept);
+        Self {
+            transitions,
+            start: id(self.start),
+            accept,
+            eof: ids[self.eof].unwrap_or(accept),
+            lazy: self.lazy.iter().filter_map(|&s| ids[s]).collect(),
+        }
+    }
+}
+
+/// A group together with the number of bytes it matched.
+type GroupMatch = (Option<Label>, usize);
+
 #[derive(Debug)]
 struct Step {
     /// The current char in the input string.
@@ -329,6 +502,8 @@ struct Step {
     step_list: Vec<usize>,
     /// The current step.
     step: usize,
+    /// Lazy branches reached in the current step, followed after all other eps-transitions.
+    lazy: Vec<(Option<Label>, State)>,
 }
 
 impl Step {
@@ -339,6 +514,7 @@ impl Step {
             consumed: 0,
             step_list: (0..num_states).map(|_| 0).collect(),
             step: 1,
+            lazy: vec![],
         }
     }
 
@@ -364,10 +540,16 @@ impl NFA {
         &self,
         step: &mut Step,
         list: &mut Vec<(Option<Label>, State)>,
-        matches: &mut HashMap<Option<Label>, usize>,
+        matches: &mut Vec<GroupMatch>,
         group: Option<Label>,
         state: State,
     ) {
+        // The accept state is shared by all groups in an `NFASet`,
+        // so it must be recorded for every group that reaches it.
+        if state == self.accept {
+            matches.push((group, step.consumed));
+        }
+
         if step.is_visited(state) {
             return;
         };
@@ -375,7 +557,11 @@ impl NFA {
         match &self[state] {
             &Transition::Split(e1, e2) => {
                 if let Some(e1) = e1 {
-                    self.add_state(step, list, matches, group, e1);
+                    if self.lazy.contains(&state) {
+                        step.lazy.push((group, e1));
+                    } else {
+                        self.add_state(step, list, matches, group, e1);
+                    }
                 }
                 if let Some(e2) = e2 {
                     self.add_state(step, list, matches, group, e2);
@@ -385,10 +571,6 @@ impl NFA {
             Transition::Label(_, _) | Transition::Accept => {
                 step.set_visited(state);
                 list.push((group, state));
-
-                if state == self.accept {
-                    matches.insert(group, step.consumed);
-                }
             }
             Transition::Eof => {
                 step.set_visited(state);
@@ -404,7 +586,7 @@ impl NFA {
         step: &mut Step,
         current_list: &Vec<(Option<Label>, State)>,
         next_list: &mut Vec<(Option<Label>, State)>,
-        matches: &mut HashMap<Option<Label>, usize>,
+        matches: &mut Vec<GroupMatch>,
     ) {
         debug_assert!(next_list.is_empty());
 
@@ -434,35 +616,95 @@ impl From<(Option<Label>, usize)> for Match {
     }
 }
 
-impl Language for NFA {
-    fn is_match(&self, input: &str) -> Vec<Match> {
+impl NFA {
+    /// Find every prefix of `input` accepted by each group, not only the longest.
+    /// The matches are ordered by length.
+    ///
+    /// Useful to find ambiguous patterns, e.g. `A|AA|AAA` matches `"AAA"` three times.
+    #[must_use]
+    pub fn all_matches(&self, input: &str) -> Vec<Match> {
+        let (mut matches, eof) = self.run(input);
+        matches.extend(eof);
+        matches.sort_by_key(|&(group, size)| (size, group.map(<&str>::from)));
+        matches.dedup();
+        matches.into_iter().map(Match::from).collect()
+    }
+
+    /// Simulate the NFA on `input`.
+    /// Returns each time the accept state was reached,
+    /// and the Eof states which are still active after the whole input has been read.
+    fn run(&self, input: &str) -> (Vec<GroupMatch>, Vec<GroupMatch>) {
         let mut current_list = Vec::with_capacity(self.transitions.len());
         let mut next_list = Vec::with_capacity(self.transitions.len());
 
-        let mut matches = HashMap::new();
+        let mut matches = vec![];
 
         let mut step = Step::new(self.transitions.len());
 
         // Follow any eps-closuers at the start
         self.add_state(&mut step, &mut current_list, &mut matches, None, self.start);
+        self.stop_lazy(&mut step, &mut current_list, &mut matches, 0);
 
         for c in input.chars() {
             step.next_step(c);
 
+            let matched = matches.len();
             self.step(&mut step, &current_list, &mut next_list, &mut matches);
+            self.stop_lazy(&mut step, &mut next_list, &mut matches, matched);
 
-            std::mem::swap(&mut current_list, &mut next_list);
+            core::mem::swap(&mut current_list, &mut next_list);
             next_list.truncate(0);
+            if current_list.is_empty() {
+                break;
+            }
         }
 
-        // Add any Eof states still on the stack
-        let current_list = current_list
+        // Add any Eof states still on the stack.
+        // Each NFA in an `NFASet` has its own Eof state.
+        let eof = current_list
             .into_iter()
-            .filter_map(|(group, state)| (state == self.eof).then_some((group, input.len())));
+            .filter_map(|(group, state)| {
+                matches!(self[state], Transition::Eof).then_some((group, input.len()))
+            })
+            .collect();
+
+        (matches, eof)
+    }
+
+    /// Follow the lazy branches reached in this step, after all other eps-transitions.
+    /// The states only reached through them are dropped for the groups in `matches[matched..]`,
+    /// a lazy quantifier stops repeating as soon as its group has matched.
+    fn stop_lazy(
+        &self,
+        step: &mut Step,
+        list: &mut Vec<(Option<Label>, State)>,
+        matches: &mut Vec<GroupMatch>,
+        matched: usize,
+    ) {
+        let first = list.len();
+        while let Some((group, state)) = step.lazy.pop() {
+            self.add_state(step, list, matches, group, state);
+        }
+
+        let lazy = list.split_off(first);
+        let matched = &matches[matched..];
+        list.extend(
+            lazy.into_iter()
+                .filter(|(group, _)| !matched.iter().any(|(g, _)| g == group)),
+        );
+    }
+}
+
+impl Language for NFA {
+    fn is_match(&self, input: &str) -> Vec<Match> {
+        let (matches, eof) = self.run(input);
 
+        // Only keep the longest match for each group
         matches
             .into_iter()
-            .chain(current_list)
+            .collect::<HashMap<_, _>>()
+            .into_iter()
+            .chain(eof)
             .map(|(l, s)| (l, s).into())
             .collect()
     }
@@ -477,8 +719,8 @@ impl Language for NFA {
     }
 }
 
-impl std::fmt::Display for NFA {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for NFA {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let headers = ["Type", "State", "Label", "e1", "e2"].map(String::from);
 
         let mut data = vec![];
@@ -593,6 +835,98 @@ mod tests {
         assert!(nfa.is_match(r"\n\t").is_empty());
     }
 
+    #[test]
+    fn remove_states() {
+        for pattern in ["ab", "(a|b)*c$", "a?b+|x"] {
+            let nfa = NFA::try_from_language(pattern).unwrap();
+            let reachable = nfa.remove_unreachable();
+            // The unused eof state is removed
+            let expected = nfa.transitions.len() - usize::from(!pattern.contains('$'));
+            assert_eq!(reachable.transitions.len(), expected, "Pattern: {pattern}");
+            assert_eq!(reachable.remove_dead().transitions.len(), expected);
+
+            for input in ["ab", "abc", "c", "aac", "bb", "x", ""] {
+                assert_eq!(reachable.is_match(input), nfa.is_match(input));
+            }
+        }
+
+        // 'b' leads to a loop which never accepts
+        let nfa = NFA {
+            transitions: vec![
+                Transition::Eof,
+                Transition::Accept,
+                Transition::Label(Lit::Char('a'), State(1)),
+                Transition::Label(Lit::Char('b'), State(4)),
+                Transition::Label(Lit::Char('c'), State(4)),
+                Transition::Split(Some(State(3)), Some(State(2))),
+            ],
+            start: State(5),
+            accept: State(1),
+            eof: State(0),
+            lazy: HashSet::new(),
+        };
+        let live = nfa.remove_dead();
+        assert_eq!(live.transitions.len(), 4);
+        assert!(matches!(live[live.start], Transition::Split(None, Some(_))));
+        assert_eq!(live.is_match("a"), vec![Match::NoGroup(1)]);
+        assert!(live.is_match("bc").is_empty());
+        assert_eq!(live.remove_unreachable().transitions.len(), 3);
+
+        let nothing = NFA {
+            start: State(3),
+            ..nfa
+        };
+        assert_eq!(nothing.remove_dead().transitions.len(), 2);
+    }
+
+    #[test]
+    fn all_matches() {
+        let nfa = NFA::try_from_language("A|AA|AAA").unwrap();
+        assert_eq!(nfa.is_match("AAA"), vec![Match::NoGroup(3)]);
+        assert_eq!(
+            nfa.all_matches("AAAA"),
+            vec![Match::NoGroup(1), Match::NoGroup(2), Match::NoGroup(3)]
+        );
+
+        let nfa = NFA::try_from_language("(ab)*$").unwrap();
+        assert_eq!(nfa.all_matches("abab"), vec![Match::NoGroup(4)]);
+        assert!(nfa.all_matches("aba").is_empty());
+    }
+
+    #[test]
+    fn lazy() {
+        let matches =
+            |pattern: &str, input| NFA::try_from_language(pattern).unwrap().is_match(input);
+
+        let input = r#""ab" cd "e" f"#;
+        // The body may also match the closing quote
+        assert_eq!(matches(r#""(a-z|\ |")*""#, input), vec![Match::NoGroup(11)]);
+        assert_eq!(matches(r#""(a-z|\ |")*?""#, input), vec![Match::NoGroup(4)]);
+
+        assert_eq!(matches("a+?", "aaa"), vec![Match::NoGroup(1)]);
+        assert_eq!(matches("a*?", "aaa"), vec![Match::NoGroup(0)]);
+        assert_eq!(matches("a??", "a"), vec![Match::NoGroup(0)]);
+        assert_eq!(matches("a??b", "ab"), vec![Match::NoGroup(2)]);
+        // Only the lazy part stops early
+        assert_eq!(matches("a+?b+", "aabbb"), vec![Match::NoGroup(5)]);
+        assert_eq!(matches("(a|b)*?b", "abab"), vec![Match::NoGroup(2)]);
+        // A lazy quantifier inside a loop only stops its own repetitions,
+        // the match of length 0 at the start doesn't stop `a*?`
+        assert_eq!(matches("(a*?b)*", "abaab"), vec![Match::NoGroup(5)]);
+        assert_eq!(matches("(a*?b)+", "abaab"), vec![Match::NoGroup(5)]);
+        assert_eq!(matches("(a*?b)*", "aab"), vec![Match::NoGroup(3)]);
+        assert_eq!(matches("(a*?b)*", "c"), vec![Match::NoGroup(0)]);
+        assert_eq!(matches("(ab+?)*", "abbab"), vec![Match::NoGroup(2)]);
+
+        let nfa = NFA::try_from_language("a+?").unwrap();
+        assert_eq!(nfa.all_matches("aaa"), vec![Match::NoGroup(1)]);
+        let nfa = NFA::try_from_language("(a*?b)*").unwrap();
+        assert_eq!(
+            nfa.all_matches("abaab"),
+            vec![Match::NoGroup(0), Match::NoGroup(2), Match::NoGroup(5)]
+        );
+    }
+
     #[test]
     fn eof() {
         let nfa: NFA = NFA::try_from_language("a$").unwrap();
@@ -610,29 +944,4 @@ mod tests {
         let nfa: NFA = NFA::try_from_language("$").unwrap();
         assert_eq!(nfa.is_match(""), vec![Match::NoGroup(0)]);
     }
-
-    extern crate test;
-    use test::Bencher;
-
-    /// This is not a good benchmark.
-    /// It's a simple test to check whether a particular optimization has any effect.
-    ///
-    /// Previous iterations:
-    /// - 10,510,434 ns/iter (+/- 210,810)
-    /// - 10,631,001 ns/iter (+/- 198,757)   After chaning is_match to return `Option<usize>`.
-    /// - 18,495,653 ns/iter (+/- 1,023,148) After adding capturing groups and char-classes.
-    /// - 11,306,364 ns/iter (+/- 419,921)   Add &mut to matches HashMap, avoids redundant loop.
-    #[bench]
-    fn bench_matches(b: &mut Bencher) {
-        const N: usize = 250;
-
-        let pattern = "A?".repeat(N) + &"A".repeat(N);
-        let input = &"A".repeat(N);
-
-        let nfa: NFA = NFA::try_from_language(pattern).unwrap();
-
-        assert!(!nfa.is_match(input).is_empty());
-
-        b.iter(|| !nfa.is_match(input).is_empty());
-    }
 }
@@ -1,4 +1,13 @@
-use crate::language::{Label, Language, LanguageError, Match};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    dfa::TokenDFA,
+    language::{Label, Language, LanguageError, Match},
+};
 
 use super::{nfa::Transition, state::State, NFA};
 
@@ -7,10 +16,21 @@ use super::{nfa::Transition, state::State, NFA};
 ///
 /// The constructed NFA returns the label for the NFA whenever a match is detected.
 #[derive(Debug)]
-pub struct NFASet(pub NFA);
+pub struct NFASet {
+    pub nfa: NFA,
+    /// Labels in the order the NFAs were given.
+    /// Earlier labels have higher priority.
+    pub labels: Vec<Label>,
+}
 
 impl NFASet {
+    /// Combine `nfas` into a single NFA.
+    ///
+    /// The order of `nfas` decides the priority between them,
+    /// see [`NFASet::longest_match`].
     pub fn build(mut nfas: Vec<(Label, NFA)>) -> Result<Self, String> {
+        let labels = nfas.iter().map(|(label, _)| *label).collect();
+
         let mut nfa = if let Some((marker, mut nfa)) = nfas.pop() {
             nfa.new_group_state(marker);
             nfa
@@ -56,32 +76,83 @@ impl NFASet {
             }
 
             nfa.transitions.append(&mut next_nfa.transitions);
+            nfa.lazy
+                .extend(next_nfa.lazy.iter().map(|s| State(s.0 + add_state)));
             let start =
                 nfa.new_split_state(Some(nfa.start), Some(State(next_nfa.start.0 + add_state)));
             nfa.start = start;
         }
 
-        Ok(Self(nfa))
+        Ok(Self { nfa, labels })
+    }
+
+    /// Position of `label` in the list given to [`NFASet::build`].
+    /// A lower number means a higher priority.
+    #[must_use]
+    pub fn priority(&self, label: Label) -> Option<usize> {
+        self.labels.iter().position(|l| *l == label)
+    }
+
+    /// Find the longest match.
+    /// If several NFAs match the same length the one given first to [`NFASet::build`] wins,
+    /// e.g. a keyword declared before an identifier.
+    #[must_use]
+    pub fn longest_match(&self, input: &str) -> Option<Match> {
+        self.is_match(input)
+            .into_iter()
+            .min_by_key(|m| (core::cmp::Reverse(m.match_size()), self.match_priority(m)))
+    }
+
+    /// Every prefix of `input` accepted by each NFA, see [`NFA::all_matches`].
+    /// Matches of the same length are ordered by the priority of their labels.
+    #[must_use]
+    pub fn all_matches(&self, input: &str) -> Vec<Match> {
+        let mut matches = self.nfa.all_matches(input);
+        matches.sort_by_key(|m| (m.match_size(), self.match_priority(m)));
+        matches
+    }
+
+    /// Compile into a [`TokenDFA`] without any limit on the number of states.
+    /// Use [`TokenDFA::build`] for untrusted patterns.
+    #[must_use]
+    pub fn determinize(&self) -> TokenDFA {
+        match TokenDFA::build(self, usize::MAX) {
+            Ok(dfa) => dfa,
+            Err(e) => unreachable!("{e}"),
+        }
+    }
+
+    fn match_priority(&self, m: &Match) -> usize {
+        match m {
+            Match::Group(label, _) => self.priority(*label).unwrap_or(usize::MAX),
+            Match::NoGroup(_) => usize::MAX,
+        }
     }
 }
 
 impl Language for NFASet {
+    /// The matches are ordered by the priority of their labels.
     fn is_match(&self, input: &str) -> Vec<Match> {
-        self.0.is_match(input)
+        let mut matches = self.nfa.is_match(input);
+        matches.sort_by_key(|m| self.match_priority(m));
+        matches
     }
 
     fn to_language(&self) -> String {
-        self.0.to_language()
+        self.nfa.to_language()
     }
 
     fn try_from_language<S: AsRef<str>>(source: S) -> Result<Self, LanguageError> {
-        Ok(Self(NFA::try_from_language(source)?))
+        Ok(Self {
+            nfa: NFA::try_from_language(source)?,
+            labels: vec![],
+        })
     }
 }
 
-impl std::fmt::Display for NFASet {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+impl core::fmt::Display for NFASet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.nfa.fmt(f)
     }
 }
 
@@ -130,4 +201,64 @@ mod tests {
         assert!(nfa.is_match("").is_empty());
         assert!(nfa.is_match("!hello").is_empty());
     }
+
+    #[test]
+    fn priority() {
+        let keyword = || ("keyword".into(), NFA::try_from_language("if|else").unwrap());
+        let ident = || ("ident".into(), NFA::try_from_language("(a-z)+").unwrap());
+
+        let nfa = NFASet::build(vec![keyword(), ident()]).unwrap();
+        assert_eq!(
+            nfa.is_match("if"),
+            vec![
+                Match::Group("keyword".into(), 2),
+                Match::Group("ident".into(), 2)
+            ]
+        );
+        assert_eq!(
+            nfa.longest_match("if"),
+            Some(Match::Group("keyword".into(), 2))
+        );
+        assert_eq!(
+            nfa.longest_match("iff"),
+            Some(Match::Group("ident".into(), 3))
+        );
+        assert_eq!(nfa.longest_match("!"), None);
+
+        let nfa = NFASet::build(vec![ident(), keyword()]).unwrap();
+        assert_eq!(
+            nfa.is_match("else"),
+            vec![
+                Match::Group("ident".into(), 4),
+                Match::Group("keyword".into(), 4)
+            ]
+        );
+        assert_eq!(
+            nfa.longest_match("else"),
+            Some(Match::Group("ident".into(), 4))
+        );
+    }
+
+    #[test]
+    fn all_matches() {
+        let nfa = NFASet::build(vec![
+            ("a".into(), NFA::try_from_language("A|AA|AAA").unwrap()),
+            ("b".into(), NFA::try_from_language("A*").unwrap()),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            nfa.all_matches("AAA"),
+            vec![
+                Match::Group("b".into(), 0),
+                Match::Group("a".into(), 1),
+                Match::Group("b".into(), 1),
+                Match::Group("a".into(), 2),
+                Match::Group("b".into(), 2),
+                Match::Group("a".into(), 3),
+                Match::Group("b".into(), 3),
+            ]
+        );
+        assert_eq!(nfa.is_match("AAA").len(), 2);
+    }
 }
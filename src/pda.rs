@@ -0,0 +1,232 @@
+//! Pushdown automata.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    cfg::{Symbol, CFG},
+    nfa::State,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PDAError {
+    /// The simulation visited more than `steps` configurations without accepting.
+    TooManySteps { steps: usize },
+}
+
+impl std::fmt::Display for PDAError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManySteps { steps } => {
+                write!(f, "Gave up after visiting {steps} configurations")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PDAError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PDATransition {
+    pub from: State,
+    /// The input symbol to read, `None` for an eps-transition.
+    pub input: Option<String>,
+    /// The symbol to pop from the top of the stack, `None` to leave the stack unchanged.
+    pub pop: Option<Symbol>,
+    /// The symbols to push onto the stack, the first symbol ends up on top.
+    pub push: Vec<Symbol>,
+    pub to: State,
+}
+
+/// A nondeterministic pushdown automaton.
+///
+/// The input is accepted if all input can be read,
+/// ending in an accepting state with an empty stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PDA {
+    pub transitions: Vec<PDATransition>,
+    pub start: State,
+    pub accept: HashSet<State>,
+    /// The stack at the start, the first symbol is the top.
+    pub initial_stack: Vec<Symbol>,
+}
+
+impl PDA {
+    /// Maximum number of configurations used by [`PDA::accepts`].
+    pub const DEFAULT_MAX_STEPS: usize = 100_000;
+
+    /// Check if `input` is accepted by exploring all configurations breadth first.
+    ///
+    /// # Errors
+    ///
+    /// The search might never end, e.g. for a PDA built from a left-recursive grammar,
+    /// so it fails after visiting `max_steps` configurations.
+    /// [`CNF::accepts`](crate::cfg::CNF::accepts) always finishes.
+    pub fn accepts<S: AsRef<str>>(&self, input: &[S], max_steps: usize) -> Result<bool, PDAError> {
+        // The top of the stack is the last element
+        let stack = self.initial_stack.iter().rev().cloned().collect::<Vec<_>>();
+        let start = (self.start, 0, stack);
+
+        let mut visited = HashSet::from([start.clone()]);
+        let mut queue = VecDeque::from([start]);
+        let mut steps = 0;
+
+        while let Some((state, position, stack)) = queue.pop_front() {
+            if position == input.len() && stack.is_empty() && self.accept.contains(&state) {
+                return Ok(true);
+            }
+
+            steps += 1;
+            if steps > max_steps {
+                return Err(PDAError::TooManySteps { steps: max_steps });
+            }
+
+            for t in self.transitions.iter().filter(|t| t.from == state) {
+                let position = match &t.input {
+                    None => position,
+                    Some(symbol) if input.get(position).map(AsRef::as_ref) == Some(symbol) => {
+                        position + 1
+                    }
+                    Some(_) => continue,
+                };
+
+                let mut stack = stack.clone();
+                if let Some(pop) = &t.pop {
+                    if stack.last() != Some(pop) {
+                        continue;
+                    }
+                    stack.pop();
+                }
+                stack.extend(t.push.iter().rev().cloned());
+
+                let next = (t.to, position, stack);
+                if visited.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl From<&CFG> for PDA {
+    /// A PDA with a single state which expands the non-terminal on top of the stack,
+    /// or reads the terminal on top of the stack from the input.
+    fn from(cfg: &CFG) -> Self {
+        let state = State(0);
+        let mut transitions = cfg
+            .productions
+            .iter()
+            .map(|p| PDATransition {
+                from: state,
+                input: None,
+                pop: Some(Symbol::NonTerminal(p.head.clone())),
+                push: p.body.clone(),
+                to: state,
+            })
+            .collect::<Vec<_>>();
+
+        let mut terminals = cfg
+            .productions
+            .iter()
+            .flat_map(|p| &p.body)
+            .filter_map(|s| match s {
+                Symbol::Terminal(t) => Some(t),
+                Symbol::NonTerminal(_) => None,
+            })
+            .collect::<Vec<_>>();
+        terminals.sort_unstable();
+        terminals.dedup();
+
+        transitions.extend(terminals.into_iter().map(|t| PDATransition {
+            from: state,
+            input: Some(t.clone()),
+            pop: Some(Symbol::Terminal(t.clone())),
+            push: vec![],
+            to: state,
+        }));
+
+        Self {
+            transitions,
+            start: state,
+            accept: HashSet::from([state]),
+            initial_stack: vec![Symbol::NonTerminal(cfg.start.clone())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<String> {
+        input.chars().map(String::from).collect()
+    }
+
+    #[test]
+    fn from_cfg() {
+        let cfg: CFG = r#"
+            <expr> ::= <term> "+" <expr> | <term>
+            <term> ::= "x" | "(" <expr> ")"
+        "#
+        .parse()
+        .unwrap();
+        let pda = PDA::from(&cfg);
+        let cnf = cfg.to_cnf();
+
+        for input in ["x", "x+x", "(x+x)+x", "((x))", "", "x+", ")x", "(x"] {
+            assert_eq!(
+                pda.accepts(&tokens(input), PDA::DEFAULT_MAX_STEPS),
+                Ok(cnf.accepts(&tokens(input))),
+                "Input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn max_steps() {
+        // The stack grows forever when expanding `<s>`
+        let cfg: CFG = r#"<s> ::= <s> "a" | "b""#.parse().unwrap();
+        let pda = PDA::from(&cfg);
+
+        assert_eq!(pda.accepts(&["b", "a"], 1000), Ok(true));
+        assert_eq!(
+            pda.accepts(&["a"], 1000),
+            Err(PDAError::TooManySteps { steps: 1000 })
+        );
+        assert!(!cfg.to_cnf().accepts(&["a"]));
+    }
+
+    #[test]
+    fn two_states() {
+        // a^n b^n, with n > 0
+        let t = |from, input: &str, pop: Option<&str>, push: &[&str], to| PDATransition {
+            from: State(from),
+            input: Some(input.to_string()),
+            pop: pop.map(|s| Symbol::Terminal(s.to_string())),
+            push: push
+                .iter()
+                .map(|s| Symbol::Terminal(s.to_string()))
+                .collect(),
+            to: State(to),
+        };
+        let pda = PDA {
+            transitions: vec![
+                t(0, "a", None, &["A"], 0),
+                t(0, "b", Some("A"), &[], 1),
+                t(1, "b", Some("A"), &[], 1),
+            ],
+            start: State(0),
+            accept: HashSet::from([State(1)]),
+            initial_stack: vec![],
+        };
+
+        let accepts = |input| pda.accepts(&tokens(input), 100).unwrap();
+        assert!(accepts("ab"));
+        assert!(accepts("aaabbb"));
+        assert!(!accepts(""));
+        assert!(!accepts("aab"));
+        assert!(!accepts("abb"));
+        assert!(!accepts("abab"));
+    }
+}
@@ -1,3 +1,5 @@
+use alloc::{string::String, vec::Vec};
+
 /// Construct a table which can be pretty printed.
 ///
 /// Formats the contents as:
@@ -15,8 +17,8 @@ impl<const COLUMNS: usize> Table<COLUMNS> {
     }
 }
 
-impl<const COLUMNS: usize> std::fmt::Display for Table<COLUMNS> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<const COLUMNS: usize> core::fmt::Display for Table<COLUMNS> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut maxs = [0usize; COLUMNS];
 
         for (i, v) in self.headers.iter().enumerate() {
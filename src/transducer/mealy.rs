@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::nfa::State;
+
+use super::{symbol_index, Moore, RunError};
+
+/// A [Mealy machine], the output depends on the current state and the input symbol.
+///
+/// [Mealy machine]: <https://en.wikipedia.org/wiki/Mealy_machine>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mealy<I, O> {
+    /// The input symbols.
+    pub alphabet: Vec<I>,
+    /// `transitions[state][symbol]` is the next state and the output
+    /// after reading `alphabet[symbol]` in `state`.
+    pub transitions: Vec<Vec<Option<(State, O)>>>,
+    pub start: State,
+}
+
+impl<I: PartialEq, O: Clone> Mealy<I, O> {
+    /// Returns one output for each symbol in `input`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a symbol is not in the alphabet or has no transition,
+    /// or if a state is missing from `transitions`.
+    pub fn run(&self, input: impl IntoIterator<Item = I>) -> Result<Vec<O>, RunError> {
+        let mut state = self.start;
+        let mut output = vec![];
+
+        for (position, symbol) in input.into_iter().enumerate() {
+            let class = symbol_index(&self.alphabet, &symbol, position)?;
+            let row = self
+                .transitions
+                .get(state.0)
+                .ok_or(RunError::InvalidState { state })?;
+            let (next, out) = row
+                .get(class)
+                .and_then(Option::as_ref)
+                .ok_or(RunError::NoTransition { state, position })?;
+            output.push(out.clone());
+            state = *next;
+        }
+
+        Ok(output)
+    }
+}
+
+impl<I: Clone, O: Clone + Eq + Hash> Mealy<I, O> {
+    /// Convert into a [`Moore`] machine which outputs `initial` in the start state.
+    ///
+    /// Each state is split into one state for each output on the transitions into it,
+    /// so the Moore machine produces `initial` followed by the output of this machine.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a state reached from the start state is missing from `transitions`.
+    pub fn to_moore(&self, initial: O) -> Result<Moore<I, O>, RunError> {
+        let mut ids = HashMap::from([((self.start, initial.clone()), State(0))]);
+        let mut states = vec![(self.start, initial)];
+        let mut transitions = vec![];
+
+        // `states` grows while we iterate over it
+        while transitions.len() < states.len() {
+            let (state, _) = states[transitions.len()];
+            let row = self
+                .transitions
+                .get(state.0)
+                .ok_or(RunError::InvalidState { state })?
+                .iter()
+                .map(|next| {
+                    let key = next.clone()?;
+                    Some(*ids.entry(key.clone()).or_insert_with(|| {
+                        states.push(key);
+                        State(states.len() - 1)
+                    }))
+                })
+                .collect();
+            transitions.push(row);
+        }
+
+        Ok(Moore {
+            alphabet: self.alphabet.clone(),
+            transitions,
+            outputs: states.into_iter().map(|(_, output)| output).collect(),
+            start: State(0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Outputs `true` when the input changes from `false` to `true`.
+    fn rising_edge() -> Mealy<bool, bool> {
+        let (low, high) = (State(0), State(1));
+        Mealy {
+            alphabet: vec![false, true],
+            transitions: vec![
+                vec![Some((low, false)), Some((high, true))],
+                vec![Some((low, false)), Some((high, false))],
+            ],
+            start: low,
+        }
+    }
+
+    #[test]
+    fn run() {
+        let mealy = rising_edge();
+        assert_eq!(
+            mealy.run([true, true, false, true, false]),
+            Ok(vec![true, false, false, true, false])
+        );
+        assert_eq!(mealy.run([]), Ok(vec![]));
+
+        let mut partial = mealy.clone();
+        partial.transitions[1][0] = None;
+        assert_eq!(
+            partial.run([true, false]),
+            Err(RunError::NoTransition {
+                state: State(1),
+                position: 1
+            })
+        );
+
+        let chars = Mealy {
+            alphabet: vec!['a'],
+            transitions: vec![vec![Some((State(0), 'b'))]],
+            start: State(0),
+        };
+        assert_eq!(
+            chars.run("aax".chars()),
+            Err(RunError::UnknownSymbol { position: 2 })
+        );
+
+        let dangling = Mealy {
+            alphabet: vec!['a'],
+            transitions: vec![vec![Some((State(1), 'b'))]],
+            start: State(0),
+        };
+        assert_eq!(
+            dangling.run("aa".chars()),
+            Err(RunError::InvalidState { state: State(1) })
+        );
+    }
+
+    #[test]
+    fn to_moore() {
+        let mealy = rising_edge();
+        let moore = mealy.to_moore(false).unwrap();
+        assert_eq!(moore.outputs.len(), 3);
+
+        for input in [
+            vec![],
+            vec![true],
+            vec![false, true, true, false, true],
+            vec![true, false, true, true, true, false],
+        ] {
+            let mut expected = vec![false];
+            expected.extend(mealy.run(input.clone()).unwrap());
+            assert_eq!(moore.run(input.clone()).unwrap(), expected);
+            assert_eq!(
+                moore.to_mealy().unwrap().run(input.clone()),
+                mealy.run(input)
+            );
+        }
+
+        let dangling = Mealy {
+            alphabet: vec!['a'],
+            transitions: vec![vec![Some((State(1), 'b'))]],
+            start: State(0),
+        };
+        assert_eq!(
+            dangling.to_moore('x'),
+            Err(RunError::InvalidState { state: State(1) })
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Finite-state transducers, automata which produce an output for each input symbol.
+
+mod mealy;
+mod moore;
+
+pub use mealy::Mealy;
+pub use moore::Moore;
+
+use crate::nfa::State;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunError {
+    /// The symbol at `position` in the input is not in the alphabet.
+    UnknownSymbol { position: usize },
+    /// `state` has no transition for the symbol at `position` in the input.
+    NoTransition { state: State, position: usize },
+    /// `state` is not a state of the machine, or has no output.
+    InvalidState { state: State },
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownSymbol { position } => {
+                write!(f, "Unknown symbol at position {position}")
+            }
+            Self::NoTransition { state, position } => {
+                write!(f, "No transition from state {state} at position {position}")
+            }
+            Self::InvalidState { state } => write!(f, "Invalid state {state}"),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// Returns the index of `symbol` in `alphabet`.
+fn symbol_index<I: PartialEq>(
+    alphabet: &[I],
+    symbol: &I,
+    position: usize,
+) -> Result<usize, RunError> {
+    alphabet
+        .iter()
+        .position(|s| s == symbol)
+        .ok_or(RunError::UnknownSymbol { position })
+}
@@ -0,0 +1,146 @@
+use crate::nfa::State;
+
+use super::{symbol_index, Mealy, RunError};
+
+/// A [Moore machine], the output only depends on the current state.
+///
+/// [Moore machine]: <https://en.wikipedia.org/wiki/Moore_machine>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moore<I, O> {
+    /// The input symbols.
+    pub alphabet: Vec<I>,
+    /// `transitions[state][symbol]` is the next state after reading `alphabet[symbol]` in `state`.
+    pub transitions: Vec<Vec<Option<State>>>,
+    /// The output of each state.
+    pub outputs: Vec<O>,
+    pub start: State,
+}
+
+impl<I: PartialEq, O: Clone> Moore<I, O> {
+    /// Returns the output of the start state followed by the output of each state reached,
+    /// one more output than there are symbols in `input`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a symbol is not in the alphabet or has no transition,
+    /// or if a state is missing from `transitions` or `outputs`.
+    pub fn run(&self, input: impl IntoIterator<Item = I>) -> Result<Vec<O>, RunError> {
+        let mut state = self.start;
+        let mut output = vec![self.output(state)?];
+
+        for (position, symbol) in input.into_iter().enumerate() {
+            let class = symbol_index(&self.alphabet, &symbol, position)?;
+            let row = self
+                .transitions
+                .get(state.0)
+                .ok_or(RunError::InvalidState { state })?;
+            state = row
+                .get(class)
+                .copied()
+                .flatten()
+                .ok_or(RunError::NoTransition { state, position })?;
+            output.push(self.output(state)?);
+        }
+
+        Ok(output)
+    }
+}
+
+impl<I, O: Clone> Moore<I, O> {
+    fn output(&self, state: State) -> Result<O, RunError> {
+        self.outputs
+            .get(state.0)
+            .cloned()
+            .ok_or(RunError::InvalidState { state })
+    }
+}
+
+impl<I: Clone, O: Clone> Moore<I, O> {
+    /// Convert into a [`Mealy`] machine which outputs the output of the state it moves to.
+    ///
+    /// The Mealy machine produces the same output without the output of the start state.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a transition leads to a state missing from `transitions` or `outputs`.
+    pub fn to_mealy(&self) -> Result<Mealy<I, O>, RunError> {
+        let next = |state: State| {
+            if state.0 >= self.transitions.len() {
+                return Err(RunError::InvalidState { state });
+            }
+            Ok((state, self.output(state)?))
+        };
+
+        Ok(Mealy {
+            alphabet: self.alphabet.clone(),
+            transitions: self
+                .transitions
+                .iter()
+                .map(|row| row.iter().map(|e| e.map(next).transpose()).collect())
+                .collect::<Result<_, _>>()?,
+            start: self.start,
+        })
+    }
+}
+
+impl<I: Clone, O: Clone> TryFrom<&Moore<I, O>> for Mealy<I, O> {
+    type Error = RunError;
+
+    fn try_from(moore: &Moore<I, O>) -> Result<Self, Self::Error> {
+        moore.to_mealy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run() {
+        // Parity of the number of 1s read so far
+        let moore = Moore {
+            alphabet: vec!['0', '1'],
+            transitions: vec![
+                vec![Some(State(0)), Some(State(1))],
+                vec![Some(State(1)), Some(State(0))],
+            ],
+            outputs: vec!["even", "odd"],
+            start: State(0),
+        };
+
+        assert_eq!(moore.run("".chars()), Ok(vec!["even"]));
+        assert_eq!(
+            moore.run("1101".chars()),
+            Ok(vec!["even", "odd", "even", "even", "odd"])
+        );
+        assert_eq!(
+            moore.run("12".chars()),
+            Err(RunError::UnknownSymbol { position: 1 })
+        );
+
+        let mut short = moore.clone();
+        short.outputs.pop();
+        assert_eq!(
+            short.run("01".chars()),
+            Err(RunError::InvalidState { state: State(1) })
+        );
+        assert_eq!(
+            short.to_mealy(),
+            Err(RunError::InvalidState { state: State(1) })
+        );
+
+        let mut dangling = moore.clone();
+        dangling.transitions[1][0] = Some(State(2));
+        dangling.outputs.push("none");
+        assert_eq!(
+            dangling.to_mealy(),
+            Err(RunError::InvalidState { state: State(2) })
+        );
+
+        let mealy = Mealy::try_from(&moore).unwrap();
+        assert_eq!(
+            mealy.run("1101".chars()),
+            Ok(vec!["odd", "even", "even", "odd"])
+        );
+    }
+}